@@ -3,61 +3,450 @@ use std::path::Path;
 use rayon::iter::{ParallelBridge, ParallelIterator};
 use firecore_world_gen::*;
 
-const PARSED: &str = "output/parsed.bin";
-
 fn main() -> anyhow::Result<()> {
-    let mappings = ron::from_str(&std::fs::read_to_string("./mappings.ron")?)?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let config_path = args
+        .iter()
+        .position(|arg| arg == "--config")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+        .or_else(|| std::env::var("WORLD_GEN_CONFIG").ok())
+        .unwrap_or_else(|| config::CONFIG_FILE.to_owned());
+
+    let mut config = config::Config::load(Path::new(&config_path))?;
+
+    config.apply_env()?;
+
+    let args = config.apply_args(&args)?;
+
+    let root = config.output.dir.as_path();
+
+    if args.first().map(String::as_str) == Some("validate") {
+        let dir = args
+            .get(1)
+            .map(Path::new)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| match config.output.split {
+                true => root.join("maps"),
+                false => root.join("maps").join("files"),
+            });
+        let count = output::validate(&dir)?;
+        println!("Validated {} files in {:?}", count, dir);
+        for path in [
+            root.join("metadata.bin"),
+            root.join("scripts").join("scripts.bin"),
+        ] {
+            match path.exists() {
+                true => {
+                    output::validate_file(&path)?;
+                    println!("Validated {:?}", path);
+                }
+                false => eprintln!("Could not find {:?} to validate", path),
+            }
+        }
+        let maps = graph::read_maps(&dir)?;
+        let start = match args.get(2) {
+            Some(start) => maps
+                .keys()
+                .find(|location| &graph::node(location) == start)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("Start map {} is not in {:?}", start, dir))
+                .map(Some)?,
+            None => graph::read_spawn(&root.join("spawn.json")),
+        };
+        match start {
+            Some(start) => {
+                let unreachable = graph::unreachable(&maps, &start);
+                println!(
+                    "{} of {} maps cannot be reached from {}",
+                    unreachable.len(),
+                    maps.len(),
+                    graph::node(&start)
+                );
+                for location in unreachable {
+                    println!("    {}", graph::node(&location));
+                }
+            }
+            None => println!("Skipped reachability, as there is no start map or spawn.json"),
+        }
+        let metadata = match std::fs::read(root.join("metadata.bin")) {
+            Ok(bytes) => output::from_bytes(&bytes)?,
+            Err(..) => Default::default(),
+        };
+        let one_way = graph::one_way_warps(&maps, &metadata);
+        println!("{} warps have no warp back", one_way.len());
+        for warp in one_way {
+            println!(
+                "    {} {},{} -> {}",
+                graph::node(&warp.map),
+                warp.coords.x,
+                warp.coords.y,
+                graph::node(&warp.destination)
+            );
+        }
+        let mismatches = graph::connection_mismatches(&maps);
+        println!("{} connections are not mirrored", mismatches.len());
+        for mismatch in mismatches {
+            match mismatch.back {
+                Some(back) => println!(
+                    "    {} connects {:?} to {} at {}, which connects back at {}",
+                    graph::node(&mismatch.map),
+                    mismatch.direction,
+                    graph::node(&mismatch.neighbor),
+                    mismatch.offset,
+                    back
+                ),
+                None => println!(
+                    "    {} connects {:?} to {} at {}, which does not connect back",
+                    graph::node(&mismatch.map),
+                    mismatch.direction,
+                    graph::node(&mismatch.neighbor),
+                    mismatch.offset
+                ),
+            }
+        }
+        return Ok(());
+    }
+
+    if args.first().map(String::as_str) == Some("graph") {
+        let dir = args
+            .get(1)
+            .map(Path::new)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| match config.output.split {
+                true => root.join("maps"),
+                false => root.join("maps").join("files"),
+            });
+        let out = args
+            .get(2)
+            .map(Path::new)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| root.join("world.dot"));
+        let maps = graph::read_maps(&dir)?;
+        std::fs::write(&out, graph::dot(&maps))?;
+        println!("Wrote graph of {} maps to {:?}", maps.len(), out);
+        return Ok(());
+    }
+
+    if args.first().map(String::as_str) == Some("serve") {
+        let address = args.get(1).map(String::as_str).unwrap_or("127.0.0.1:8080");
+        return serve::serve(root, address);
+    }
+
+    if args.first().map(String::as_str) == Some("scaffold") {
+        let dir = args
+            .get(1)
+            .ok_or_else(|| anyhow::anyhow!("Usage: scaffold <dir>"))?;
+        for file in scaffold::scaffold(Path::new(dir))? {
+            println!("Wrote {}", Path::new(dir).join(file).display());
+        }
+        return Ok(());
+    }
+
+    if args.first().map(String::as_str) == Some("changelog") {
+        let (old, new) = match (args.get(1), args.get(2)) {
+            (Some(old), Some(new)) => (old, new),
+            _ => anyhow::bail!("Usage: changelog <old manifest> <new manifest>"),
+        };
+        let old = output::Manifest::load(Path::new(old))?;
+        let new = output::Manifest::load(Path::new(new))?;
+        for line in output::changelog(&old, &new) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    let source = config.source()?;
+
+    let layers = config.mapping_layers();
+
+    let mut mappings = NameMappings::load(&layers)?;
+
+    let mut inputs = layers
+        .iter()
+        .filter(|path| path.exists())
+        .map(|path| Ok((path.display().to_string(), std::fs::read(path)?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let edits_file = std::fs::read_to_string(&config.edits)?;
+
+    inputs.push((
+        config.edits.display().to_string(),
+        edits_file.as_bytes().to_vec(),
+    ));
+
+    let edits = ron::from_str(&edits_file)?;
+
+    let cache = cache::Cache::new(&config.cache);
+
+    let revision = source
+        .revision()
+        .map_err(|err| eprintln!("Could not get source revision with error {}", err))
+        .ok()
+        .flatten();
+
+    // sources without a known revision, such as unversioned directories, are keyed by name alone
+    let key = format!(
+        "parsed {} {}",
+        source.name(),
+        revision.as_deref().unwrap_or_default()
+    );
+
+    let data = {
+        let lock = cache.lock()?;
+        match cache
+            .get(&lock, &key)
+            .and_then(|bytes| postcard::from_bytes::<ParsedData>(&bytes).ok())
+        {
+            Some(data) => data,
+            None => {
+                let data = create_data(source.as_ref())?;
+                cache.put(&lock, &key, &postcard::to_allocvec(&data)?)?;
+                data
+            }
+        }
+    };
+
+    // files written next to the output for review, once the subcommands that only read are done
+    let mut review = Vec::new();
+
+    match source.text(SONGS_HEADER) {
+        Ok(songs) => {
+            let music = mappings.infer_music(&songs);
+            if !music.is_empty() {
+                review.push((
+                    root.join("mappings.music.ron"),
+                    format!("Found {} songs without a music mapping", music.len()),
+                    music_file(&music),
+                ));
+            }
+        }
+        Err(err) => eprintln!("Could not get songs with error {}", err),
+    }
+
+    if config.detect_palettes {
+        let detected = tileset::detect_palettes(source.as_ref(), &mappings, &data);
+        println!(
+            "Detected palettes for {} tilesets",
+            detected.primary.len() + detected.secondary.len()
+        );
+        for (tileset, hash) in detected.unknown.iter() {
+            eprintln!("Unknown tileset {} with hash {:#010x}", tileset, hash);
+        }
+        mappings.palettes.primary.extend(detected.primary);
+        mappings.palettes.secondary.extend(detected.secondary);
+    }
+
+    if args.iter().map(String::as_str).eq(["mappings", "check"]) {
+        let missing = mappings.check(&data);
+        print!("{}", missing);
+        println!("{} names have no mapping", missing.len());
+        if config.strict && !missing.is_empty() {
+            anyhow::bail!("Mappings are incomplete in strict mode");
+        }
+        return Ok(());
+    }
+
+    if args.iter().map(String::as_str).eq(["mappings", "coverage"]) {
+        print!("{}", mappings.coverage(&data));
+        return Ok(());
+    }
+
+    match source.text(GRAPHICS_INFO_POINTERS) {
+        Ok(pointers) => {
+            let inferred = mappings.infer_npc_groups(&pointers);
+            if !inferred.groups.is_empty() || !inferred.unknown.is_empty() {
+                review.push((
+                    root.join("mappings.skeleton.ron"),
+                    format!(
+                        "Inferred {} NPC groups and could not infer {}",
+                        inferred.groups.len(),
+                        inferred.unknown.len()
+                    ),
+                    inferred.skeleton(),
+                ));
+            }
+            if config.infer_npc_groups {
+                println!("Using {} inferred NPC groups", inferred.groups.len());
+                mappings.npcs.groups.extend(inferred.groups);
+            }
+        }
+        Err(err) => eprintln!("Could not get NPC graphics with error {}", err),
+    }
+
+    if args.first().map(String::as_str) == Some("assets") {
+        let dir = args
+            .get(1)
+            .map(Path::new)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| root.join("tilesets"));
+        let atlases = assets::tileset_atlases(source.as_ref(), &mappings, &data);
+        let count = assets::write_atlases(&dir, &atlases)?;
+        println!("Wrote {} tileset atlases to {:?}", count, dir);
+        return Ok(());
+    }
+
+    for (file, found, contents) in review {
+        eprintln!("{}, writing them to {:?} for review", found, file);
+        std::fs::create_dir_all(root)?;
+        std::fs::write(file, contents)?;
+    }
+
+    if config.prompt && prompt::is_interactive() {
+        if let Some(path) = &config.user_mappings {
+            let missing = mappings.check(&data);
+            let added = prompt::resolve(&mut mappings, &missing, path)?;
+            if added > 0 {
+                println!("Wrote {} mappings to {:?}", added, path);
+            }
+        }
+    }
+
+    let progression = progression::ProgressionGraph::new(&data);
 
-    let edits = ron::from_str(&std::fs::read_to_string("./edits.ron")?)?;
+    let mut registry = registry::Registry::from_source(source.as_ref(), &data.scripts)?;
 
-    fn load() -> anyhow::Result<ParsedData> {
-        anyhow::Result::<ParsedData>::Ok(postcard::from_bytes::<ParsedData>(&std::fs::read(PARSED)?)?)
+    for remapped in registry.reserve(&config.registry)? {
+        eprintln!("Remapped reserved {}", remapped);
     }
 
-    let data = load().or_else::<anyhow::Error, _>(|_| {
-        let data = create_data()?;
-        std::fs::write(PARSED, &postcard::to_allocvec(&data)?)?;
-        Ok(data)
-    })?;
+    let mut locations = data
+        .maps
+        .iter()
+        .map(|map| {
+            let id = map.key().clone();
+            let location = convert::location(&mappings, &data, &id);
+            (id, location)
+        })
+        .collect::<std::collections::BTreeMap<_, _>>();
 
-    let data = compile(mappings, edits, data).unwrap();
+    let groups = output::GroupIndex::new(&mappings, &data);
 
-    let root = Path::new("output");
+    let conversion = config
+        .converter()?
+        .mappings(mappings)
+        .edits(edits)
+        .build()
+        .convert(source.as_ref(), data)?;
+
+    std::fs::create_dir_all(root)?;
+
+    std::fs::write(
+        root.join("groups.json"),
+        serde_json::to_string_pretty(&groups)?,
+    )?;
+
+    std::fs::write(
+        root.join("generated-ids.json"),
+        serde_json::to_string_pretty(&conversion.generated)?,
+    )?;
+
+    let data = conversion.world;
+
+    // constants are only written for maps the converter kept
+    locations.retain(|_, location| data.maps.contains_key(location));
 
     let mapdir = root.join("maps");
 
-    let files = mapdir.join("files");
-    let copies = mapdir.join("copies");
+    if config.output.split {
+        let index = output::write_split(&mapdir, &data.maps)?;
+        println!("Wrote {} maps to {:?}", index.maps.len(), mapdir);
+    } else {
+        let files = mapdir.join("files");
+        let copies = mapdir.join("copies");
+
+        if files.exists() {
+            std::fs::remove_dir_all(&files)?;
+        }
+
+        std::fs::create_dir_all(&files)?;
+
+        std::fs::create_dir_all(&copies)?;
+
+        data.maps.iter().par_bridge().try_for_each::<_, anyhow::Result<()>>(|r| {
+            let location = r.0;
+            let map = r.1;
+            let data = output::to_bytes(&map)?;
+
+            let path = output::file_name(location, "world");
+
+            let file = files.join(&path);
 
-    if files.exists() {
-        std::fs::remove_dir_all(&files)?;
+            std::fs::write(file, &data)?;
+
+            let copy = copies.join(&path);
+
+            let str = ron::ser::to_string_pretty(&map, Default::default())?;
+
+            std::fs::write(copy, str.as_bytes())?;
+            Ok(())
+        })?;
     }
 
-    std::fs::create_dir_all(&files)?;
+    std::fs::write(
+        root.join("metadata.bin"),
+        output::to_bytes(&data.metadata)?,
+    )?;
 
-    std::fs::create_dir_all(&copies)?;
+    let mut manifest = output::Manifest::new(
+        &source.name(),
+        revision,
+        &inputs
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect::<Vec<_>>(),
+        &data.maps,
+    )?;
 
-    data.maps.iter().par_bridge().try_for_each::<_, anyhow::Result<()>>(|r| {
-        let location = r.0;
-        let map = r.1;
-        let data = postcard::to_allocvec(&map)?;
+    manifest.removed = conversion.removed;
 
-        let path = match location.map {
-            Some(map) => format!("{}-{}.world", map.as_str(), location.index.as_str()),
-            None => format!("{}.world", location.index.as_str()),
-        };
+    manifest.save(&root.join("manifest.json"))?;
 
-        let file = files.join(&path);
+    if config.output.localize {
+        let translations = config
+            .output
+            .translations
+            .iter()
+            .map(|(language, path)| -> anyhow::Result<_> {
+                Ok((
+                    language.clone(),
+                    ron::from_str(&std::fs::read_to_string(path)?)?,
+                ))
+            })
+            .collect::<anyhow::Result<std::collections::BTreeMap<_, _>>>()?;
+        std::fs::write(
+            root.join("names.json"),
+            serde_json::to_string_pretty(&output::name_table(
+                &config.output.language,
+                &data.names,
+                &translations,
+            ))?,
+        )?;
+    }
 
-        std::fs::write(file, &data)?;
+    std::fs::write(
+        root.join("spawn.json"),
+        serde_json::to_string_pretty(&data.spawn)?,
+    )?;
 
-        let copy = copies.join(&path);
+    std::fs::write(
+        root.join("heal_locations.json"),
+        serde_json::to_string_pretty(&data.heal_locations)?,
+    )?;
 
-        let str = ron::ser::to_string_pretty(&map, Default::default())?;
+    std::fs::write(
+        root.join("progression.json"),
+        serde_json::to_string_pretty(&serde_json::json!({
+            "maps": progression.maps,
+            "unlocks": progression.unlocks(),
+        }))?,
+    )?;
 
-        std::fs::write(copy, str.as_bytes())?;
-        Ok(())
-    })?;
+    if config.output.constants {
+        std::fs::write(root.join("locations.rs"), codegen::locations(&locations))?;
+        std::fs::write(root.join("flags.rs"), codegen::flags(&registry))?;
+    }
 
     let scriptdir = root.join("scripts");
 
@@ -67,7 +456,12 @@ fn main() -> anyhow::Result<()> {
 
     std::fs::write(
         scriptdir.join("scripts.bin"),
-        postcard::to_allocvec(&data.scripts)?,
+        output::to_bytes(&data.scripts)?,
+    )?;
+
+    std::fs::write(
+        scriptdir.join("registry.ron"),
+        ron::ser::to_string_pretty(&registry, Default::default())?,
     )?;
 
     std::fs::write(
@@ -75,5 +469,10 @@ fn main() -> anyhow::Result<()> {
         ron::ser::to_string_pretty(&data.scripts, Default::default())?,
     )?;
 
+    std::fs::write(
+        scriptdir.join("raw.ron"),
+        ron::ser::to_string_pretty(&data.raw_scripts, Default::default())?,
+    )?;
+
     Ok(())
 }
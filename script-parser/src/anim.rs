@@ -0,0 +1,207 @@
+use hashbrown::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Size of a 4 bit tile in bytes.
+pub const TILE_SIZE_4BPP: usize = 32;
+
+/// Number of tiles in FireRed's primary tilesets, which secondary tiles are placed after.
+pub const NUM_TILES_IN_PRIMARY: usize = 640;
+
+/// Tiles of a tileset that are replaced by frames over time, from pret's `tileset_anims.c`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TileAnimation {
+    /// Name of the tileset in pret's symbols, such as `General` for `gTileset_General`
+    pub tileset: String,
+    /// Name of the animation, such as `Flower`
+    pub name: String,
+    /// First tile replaced, counting the primary tiles before the secondary tiles
+    pub tile: usize,
+    /// Number of tiles replaced by each frame
+    pub tiles: usize,
+    /// Game frames between animation frames
+    pub interval: usize,
+    /// Paths of the frames' images, in the order they are shown
+    pub frames: Vec<String>,
+}
+
+/// Parses the animations queued by the tileset callbacks of `tileset_anims.c`.
+///
+/// Animations whose frames, destination or timing cannot be read are left out,
+/// as some tilesets animate through code this does not follow.
+pub fn parse_tileset_anims(file: &str) -> Vec<TileAnimation> {
+    let mut images = HashMap::new();
+    let mut lists = HashMap::<&str, Vec<&str>>::new();
+    let mut destinations = HashMap::new();
+    let mut intervals = HashMap::new();
+
+    let mut list = None;
+    let mut queue = None;
+    let mut interval = None;
+
+    for text in file.lines() {
+        let text = text.split("//").next().unwrap_or_default().trim();
+
+        if let Some(current) = list {
+            match text.starts_with('}') {
+                true => list = None,
+                false => lists.entry(current).or_default().extend(
+                    text.split(',')
+                        .map(str::trim)
+                        .filter(|frame| !frame.is_empty()),
+                ),
+            }
+            continue;
+        }
+
+        if let Some((symbol, path)) = text
+            .split_once("INCBIN_U16(\"")
+            .and_then(|(left, path)| Some((symbol(left)?, path.split_once('"')?.0)))
+        {
+            images.insert(symbol, path.replace(".4bpp", ".png"));
+        } else if text.contains("*const") && text.contains("[]") {
+            if let Some(symbol) = symbol(text) {
+                list = Some(symbol);
+                lists.insert(symbol, Vec::new());
+            }
+        } else if let Some(name) = text
+            .strip_prefix("static void QueueAnimTiles_")
+            .and_then(|text| text.split_once('(').map(|(name, ..)| name))
+        {
+            queue = Some(name);
+        } else if let Some(arguments) = text.strip_prefix("AppendTilesetAnimToBuffer(") {
+            let name = match queue {
+                Some(name) => name,
+                None => continue,
+            };
+            let arguments = arguments.trim_end_matches(';').trim_end_matches(')');
+            let frames = arguments.split('[').next().unwrap_or_default().trim();
+            let tile = arguments
+                .split_once("TILE_OFFSET_4BPP(")
+                .and_then(|(.., offset)| offset.split_once(')'))
+                .and_then(|(offset, ..)| evaluate(offset));
+            let size = arguments
+                .rsplit_once(',')
+                .and_then(|(.., size)| evaluate(size));
+            if let (Some(tile), Some(size)) = (tile, size) {
+                destinations.insert(name, (frames, tile, size / TILE_SIZE_4BPP));
+            }
+        } else if let Some(condition) = text.strip_prefix("if (timer % ") {
+            interval = condition.split_whitespace().next().and_then(evaluate);
+        }
+
+        // callbacks queue animations on the line of their condition or the one after it
+        if let Some(name) = text
+            .split_once("QueueAnimTiles_")
+            .filter(|(left, ..)| !left.contains("void"))
+            .and_then(|(.., name)| name.split_once('('))
+            .map(|(name, ..)| name)
+        {
+            if let Some(interval) = interval.take() {
+                intervals.insert(name, interval);
+            }
+        }
+    }
+
+    let mut animations = destinations
+        .into_iter()
+        .flat_map(|(name, (frames, tile, tiles))| {
+            let (tileset, animation) = name.split_once('_')?;
+            Some(TileAnimation {
+                tileset: tileset.to_owned(),
+                name: animation.to_owned(),
+                tile,
+                tiles,
+                interval: *intervals.get(name)?,
+                frames: lists
+                    .get(frames)?
+                    .iter()
+                    .map(|frame| images.get(frame).cloned())
+                    .collect::<Option<_>>()?,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    animations.sort_by(|a, b| (&a.tileset, &a.name).cmp(&(&b.tileset, &b.name)));
+
+    animations
+}
+
+/// Gets the symbol declared by a line such as `static const u16 sFrame0[] = ...`.
+fn symbol(declaration: &str) -> Option<&str> {
+    declaration
+        .split('[')
+        .next()?
+        .split_whitespace()
+        .last()
+        .map(|symbol| symbol.trim_start_matches('*'))
+}
+
+/// Evaluates sums and products of numbers and the constants pret's animations are sized with.
+fn evaluate(expression: &str) -> Option<usize> {
+    expression
+        .split('+')
+        .map(|term| {
+            term.split('*')
+                .map(|factor| {
+                    let factor = factor.trim().trim_matches(|c| c == '(' || c == ')');
+                    match factor {
+                        "TILE_SIZE_4BPP" => Some(TILE_SIZE_4BPP),
+                        "NUM_TILES_IN_PRIMARY" => Some(NUM_TILES_IN_PRIMARY),
+                        factor => match factor.strip_prefix("0x") {
+                            Some(hex) => usize::from_str_radix(hex, 16).ok(),
+                            None => factor.parse().ok(),
+                        },
+                    }
+                })
+                .product::<Option<usize>>()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_queued_animations() {
+        let file = r#"
+static const u16 sTilesetAnims_General_Flower_Frame0[] = INCBIN_U16("data/tilesets/primary/general/anim/flower/0.4bpp");
+static const u16 sTilesetAnims_General_Flower_Frame1[] = INCBIN_U16("data/tilesets/primary/general/anim/flower/1.4bpp");
+
+static const u16 *const sTilesetAnims_General_Flower[] = {
+    sTilesetAnims_General_Flower_Frame0,
+    sTilesetAnims_General_Flower_Frame1,
+    sTilesetAnims_General_Flower_Frame0
+};
+
+static void QueueAnimTiles_General_Flower(u16 timer)
+{
+    u16 i = timer % ARRAY_COUNT(sTilesetAnims_General_Flower);
+    AppendTilesetAnimToBuffer(sTilesetAnims_General_Flower[i], (u16 *)(BG_VRAM + TILE_OFFSET_4BPP(508)), 4 * TILE_SIZE_4BPP);
+}
+
+static void TilesetAnim_General(u16 timer)
+{
+    if (timer % 16 == 1)
+        QueueAnimTiles_General_Flower(timer / 16);
+}
+"#;
+        let animations = parse_tileset_anims(file);
+        assert_eq!(animations.len(), 1);
+        let flower = &animations[0];
+        assert_eq!(
+            (flower.tileset.as_str(), flower.name.as_str()),
+            ("General", "Flower")
+        );
+        assert_eq!((flower.tile, flower.tiles, flower.interval), (508, 4, 16));
+        let frame = |n: usize| format!("data/tilesets/primary/general/anim/flower/{}.png", n);
+        assert_eq!(flower.frames, [frame(0), frame(1), frame(0)]);
+    }
+
+    #[test]
+    fn evaluates_secondary_offsets() {
+        assert_eq!(evaluate("(NUM_TILES_IN_PRIMARY + 0x60)"), Some(736));
+        assert_eq!(evaluate("4 * TILE_SIZE_4BPP"), Some(128));
+    }
+}
@@ -0,0 +1,160 @@
+use std::num::ParseIntError;
+
+use hashbrown::HashMap;
+
+/// Byte that starts a formatting code, such as `{COLOR RED}`.
+pub const EXT_CTRL_CODE_BEGIN: u8 = 0xFC;
+
+/// Byte that starts a string placeholder, such as `{PLAYER}`.
+pub const PLACEHOLDER_BEGIN: u8 = 0xFD;
+
+/// pret's `charmap.txt`, which maps text to the game's character encoding.
+#[derive(Debug, Default, Clone)]
+pub struct Charmap {
+    /// Encoded bytes of each quoted character
+    pub chars: HashMap<Vec<u8>, String>,
+    /// Encoded bytes of each named code, such as `PLAYER`
+    pub codes: HashMap<String, Vec<u8>>,
+}
+
+impl Charmap {
+    pub fn parse(file: &str) -> Result<Self, CharmapError> {
+        let mut charmap = Self::default();
+        for (line, text) in file.lines().enumerate() {
+            let text = text.split('@').next().unwrap_or_default().trim();
+            if text.is_empty() {
+                continue;
+            }
+            let (name, bytes) = text.rsplit_once('=').ok_or(CharmapError::Entry(line))?;
+            let bytes = bytes
+                .split_whitespace()
+                .map(|byte| u8::from_str_radix(byte, 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| CharmapError::Byte(err, line))?;
+            let name = name.trim();
+            match name
+                .strip_prefix('\'')
+                .and_then(|name| name.strip_suffix('\''))
+            {
+                Some(char) => {
+                    charmap
+                        .chars
+                        .entry(bytes)
+                        .or_insert_with(|| char.to_owned());
+                }
+                None => {
+                    charmap.codes.insert(name.to_owned(), bytes);
+                }
+            }
+        }
+        Ok(charmap)
+    }
+
+    /// Decodes the codes in a line of text into UTF-8.
+    ///
+    /// Codes for characters become the character, `{PLAYER}` becomes `%p`,
+    /// formatting codes are removed and other placeholders are kept as they are.
+    pub fn decode(&self, text: &str) -> String {
+        let mut decoded = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some((start, after)) = rest.split_once('{') {
+            decoded.push_str(start);
+            let (code, after) = match after.split_once('}') {
+                Some(split) => split,
+                None => {
+                    decoded.push('{');
+                    rest = after;
+                    continue;
+                }
+            };
+            rest = after;
+            let name = code.split_whitespace().next().unwrap_or_default();
+            match self.codes.get(name) {
+                Some(..) if name == "PLAYER" => decoded.push_str("%p"),
+                Some(bytes) => match bytes.first() {
+                    Some(&EXT_CTRL_CODE_BEGIN) => (),
+                    Some(&PLACEHOLDER_BEGIN) => {
+                        decoded.push('{');
+                        decoded.push_str(code);
+                        decoded.push('}');
+                    }
+                    _ => match self.chars.get(bytes) {
+                        Some(char) => decoded.push_str(char),
+                        None => decoded.extend(
+                            bytes
+                                .iter()
+                                .flat_map(|byte| self.chars.get(std::slice::from_ref(byte)))
+                                .flat_map(|char| char.chars()),
+                        ),
+                    },
+                },
+                None => {
+                    decoded.push('{');
+                    decoded.push_str(code);
+                    decoded.push('}');
+                }
+            }
+        }
+        decoded.push_str(rest);
+        decoded
+    }
+}
+
+#[derive(Debug)]
+pub enum CharmapError {
+    Entry(usize),
+    Byte(ParseIntError, usize),
+}
+
+impl std::error::Error for CharmapError {}
+
+impl std::fmt::Display for CharmapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CharmapError::Entry(line) => write!(f, "Could not get charmap entry at line {}", line),
+            CharmapError::Byte(err, line) => write!(
+                f,
+                "Could not parse charmap byte at line {} with error {}",
+                line, err
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHARMAP: &str = "\
+@ Text
+' '             = 00
+'A'             = BB
+'!'             = AB
+
+PLAYER          = FD 01
+STR_VAR_1       = FD 02
+
+COLOR           = FC 01
+";
+
+    #[test]
+    fn decodes_codes_and_placeholders() {
+        let charmap = Charmap::parse(CHARMAP).unwrap();
+        assert_eq!(
+            charmap.chars.get([0xBB].as_slice()).map(String::as_str),
+            Some("A")
+        );
+        assert_eq!(
+            charmap.decode("{COLOR RED}{PLAYER} got {STR_VAR_1}!"),
+            "%p got {STR_VAR_1}!"
+        );
+    }
+
+    #[test]
+    fn rejects_entries_without_bytes() {
+        assert!(matches!(
+            Charmap::parse("'A' BB"),
+            Err(CharmapError::Entry(0))
+        ));
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+/// Place the player can fly to and respawn at, from pret's heal locations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealLocation {
+    /// pret id, such as `SPAWN_PALLET_TOWN`
+    pub id: String,
+    /// Map the player flies to, such as `MAP_PALLET_TOWN`
+    pub map: String,
+    pub x: i32,
+    pub y: i32,
+    /// Map the player respawns in after whiting out, such as a Pokemon Center
+    #[serde(default)]
+    pub respawn_map: Option<String>,
+}
+
+/// Parses `heal_locations.h`, whose first array holds the fly spots and
+/// whose second array holds the respawn map of each of them.
+pub fn parse_heal_locations(file: &str) -> Result<Vec<HealLocation>, HealLocationError> {
+    let mut locations = Vec::<HealLocation>::new();
+    let mut respawns = false;
+
+    for (line, text) in file.lines().enumerate() {
+        let text = text.trim();
+        if text == "};" && !locations.is_empty() {
+            respawns = true;
+            continue;
+        }
+        let (id, entry) = match text.strip_prefix('[').and_then(|text| text.split_once(']')) {
+            Some(split) => split,
+            None => continue,
+        };
+        let id = id.split_whitespace().next().unwrap_or_default();
+        let map = match entry
+            .split_once("MAP_GROUP(")
+            .and_then(|(.., map)| map.split_once(')'))
+        {
+            Some((map, ..)) => format!("MAP_{}", map.trim()),
+            None => continue,
+        };
+        match respawns {
+            false => {
+                let mut numbers = entry
+                    .rsplit_once(')')
+                    .map(|(.., numbers)| numbers)
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|number| number.trim().trim_end_matches(['}', ',']).trim())
+                    .filter(|number| !number.is_empty())
+                    .map(|number| {
+                        number
+                            .parse::<i32>()
+                            .map_err(|_| HealLocationError::Position(line, text.to_owned()))
+                    });
+                let x = numbers
+                    .next()
+                    .ok_or_else(|| HealLocationError::Position(line, text.to_owned()))??;
+                let y = numbers
+                    .next()
+                    .ok_or_else(|| HealLocationError::Position(line, text.to_owned()))??;
+                locations.push(HealLocation {
+                    id: id.to_owned(),
+                    map,
+                    x,
+                    y,
+                    respawn_map: None,
+                });
+            }
+            true => {
+                if let Some(location) = locations.iter_mut().find(|location| location.id == id) {
+                    location.respawn_map = Some(map);
+                }
+            }
+        }
+    }
+
+    Ok(locations)
+}
+
+#[derive(Debug)]
+pub enum HealLocationError {
+    Position(usize, String),
+}
+
+impl std::error::Error for HealLocationError {}
+
+impl std::fmt::Display for HealLocationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HealLocationError::Position(line, text) => write!(
+                f,
+                "Could not parse heal location position at line {} with text: {}",
+                line, text
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fly_spots_and_respawns() {
+        let file = "\
+static const struct HealLocation sHealLocations[] = {
+    [SPAWN_PALLET_TOWN - 1] = {MAP_GROUP(PALLET_TOWN), MAP_NUM(PALLET_TOWN), 6, 8},
+    [SPAWN_VIRIDIAN_CITY - 1] = {MAP_GROUP(VIRIDIAN_CITY), MAP_NUM(VIRIDIAN_CITY), 26, 27},
+};
+
+static const u16 sWhiteoutRespawnHealCenterMapIdxs[][2] = {
+    [SPAWN_PALLET_TOWN - 1] = {MAP_GROUP(PALLET_TOWN_PLAYERS_HOUSE_1F), MAP_NUM(PALLET_TOWN_PLAYERS_HOUSE_1F)},
+    [SPAWN_VIRIDIAN_CITY - 1] = {MAP_GROUP(VIRIDIAN_CITY_POKEMON_CENTER_1F), MAP_NUM(VIRIDIAN_CITY_POKEMON_CENTER_1F)},
+};
+";
+        let locations = parse_heal_locations(file).unwrap();
+        assert_eq!(locations.len(), 2);
+        let pallet = &locations[0];
+        assert_eq!(pallet.id, "SPAWN_PALLET_TOWN");
+        assert_eq!(pallet.map, "MAP_PALLET_TOWN");
+        assert_eq!((pallet.x, pallet.y), (6, 8));
+        assert_eq!(
+            pallet.respawn_map.as_deref(),
+            Some("MAP_PALLET_TOWN_PLAYERS_HOUSE_1F")
+        );
+        assert_eq!((locations[1].x, locations[1].y), (26, 27));
+    }
+}
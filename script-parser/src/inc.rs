@@ -136,30 +136,9 @@ pub fn parse_message_script(script: &str) -> Result<Vec<Message>, Error> {
 
                         let text = text.next().ok_or(Error::NoArguments(line))?;
 
-                        let text = text.replace("{PLAYER}", "%p");
-
-                        #[deprecated]
-                        let text = text.replace('é', "E");
-
-                        if text.contains("$") {
-                            let line = text.replace('$', "");
-                            message_lines.push(line);
-                            message_pages.push(std::mem::take(&mut message_lines));
+                        if push_message_line(text, &mut message_lines, &mut message_pages) {
                             break 'message;
                         }
-
-                        if text.contains("\\p") {
-                            let line = text.replace("\\p", "");
-                            message_lines.push(line);
-                            message_pages.push(std::mem::take(&mut message_lines));
-                        }
-
-                        for terminator in ["\\n", "\\l"] {
-                            if text.contains(terminator) {
-                                let line = text.replace(terminator, "");
-                                message_lines.push(line);
-                            }
-                        }
                     }
                     None => return Err(Error::EndOfFile("message")),
                 }
@@ -177,6 +156,76 @@ pub fn parse_message_script(script: &str) -> Result<Vec<Message>, Error> {
     Ok(messages)
 }
 
+/// Finds the text labels among the scripts of a `scripts.inc` file,
+/// which are made of `.string` lines.
+pub fn parse_strings(script: &str) -> Vec<Message> {
+    let mut messages = Vec::new();
+    let mut lines = script.lines().enumerate().peekable();
+    while let Some((line, text)) = lines.next() {
+        let mut args = text.split_whitespace();
+        let name = match args.next().and_then(|name| name.strip_suffix("::")) {
+            Some(name) => name,
+            None => continue,
+        };
+        if !lines
+            .peek()
+            .map(|(.., next)| next.trim_start().starts_with(".string"))
+            .unwrap_or_default()
+        {
+            continue;
+        }
+        let location = match location(&mut args, line) {
+            Ok(location) => location,
+            Err(..) => continue,
+        };
+        let mut message_pages = Vec::new();
+        let mut message_lines = Vec::new();
+        while let Some((.., text)) = lines.next_if(|(.., next)| next.trim_start().starts_with(".string")) {
+            let text = match text.split('"').nth(1) {
+                Some(text) => text,
+                None => continue,
+            };
+            if push_message_line(text, &mut message_lines, &mut message_pages) {
+                break;
+            }
+        }
+        if !message_lines.is_empty() {
+            message_pages.push(message_lines);
+        }
+        messages.push(Message {
+            name: name.to_owned(),
+            location,
+            text: message_pages,
+        });
+    }
+    messages
+}
+
+/// Adds a line of a `.string` to a message, returning whether the message ended.
+fn push_message_line(text: &str, lines: &mut Vec<String>, pages: &mut Vec<Vec<String>>) -> bool {
+    if text.contains("$") {
+        let line = text.replace('$', "");
+        lines.push(line);
+        pages.push(std::mem::take(lines));
+        return true;
+    }
+
+    if text.contains("\\p") {
+        let line = text.replace("\\p", "");
+        lines.push(line);
+        pages.push(std::mem::take(lines));
+    }
+
+    for terminator in ["\\n", "\\l"] {
+        if text.contains(terminator) {
+            let line = text.replace(terminator, "");
+            lines.push(line);
+        }
+    }
+
+    false
+}
+
 pub type Location = u32;
 
 fn location(args: &mut std::str::SplitWhitespace, line: usize) -> Result<Option<Location>, Error> {
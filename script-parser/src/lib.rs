@@ -1,2 +1,6 @@
+pub mod anim;
+pub mod charmap;
+pub mod heal;
 pub mod inc;
+pub mod trade;
 pub mod trainer;
\ No newline at end of file
@@ -0,0 +1,136 @@
+use hashbrown::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Pokemon an NPC trades to the player, from pret's `InGameTrade` table.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InGameTrade {
+    pub nickname: String,
+    pub species: String,
+    pub held_item: String,
+    /// Name of the pokemon's original trainer
+    pub ot_name: String,
+    /// Species the NPC wants in return
+    pub requested_species: String,
+}
+
+/// Parses the entries of the `InGameTrade` array, keyed by their id, such as `INGAME_TRADE_MR_MIME`.
+pub fn parse_trades(file: &str) -> Result<HashMap<String, InGameTrade>, TradeError> {
+    let lines = file
+        .lines()
+        .enumerate()
+        .skip_while(|(.., text)| !text.to_ascii_lowercase().contains("ingametrade"))
+        .skip(1);
+
+    let mut id = None;
+    let mut current = InGameTrade::default();
+
+    let mut trades = HashMap::new();
+
+    for (line, text) in lines {
+        let text = text.split("//").next().unwrap_or_default().trim();
+        match text {
+            "" | "{" => (),
+            "};" => break,
+            "}," | "}" => {
+                let id = id.take().ok_or(TradeError::BracketParse(line))?;
+                trades.insert(id, std::mem::take(&mut current));
+            }
+            _ => match id.is_some() {
+                false => {
+                    let id_text = text
+                        .strip_prefix('[')
+                        .and_then(|text| text.split_once(']'))
+                        .ok_or(TradeError::BracketParse(line))?
+                        .0;
+                    id = Some(id_text.trim().to_owned());
+                }
+                true => {
+                    let (left, right) = text
+                        .split_once('=')
+                        .ok_or_else(|| TradeError::FieldParse(line, text.to_owned()))?;
+                    let right = right.trim().trim_end_matches(',');
+                    let string = || {
+                        right
+                            .split('"')
+                            .nth(1)
+                            .map(str::to_owned)
+                            .ok_or_else(|| TradeError::FieldParse(line, right.to_owned()))
+                    };
+                    match left.trim() {
+                        ".nickname" => current.nickname = string()?,
+                        ".species" => current.species = right.to_owned(),
+                        ".heldItem" => current.held_item = right.to_owned(),
+                        ".otName" => current.ot_name = string()?,
+                        ".requestedSpecies" => current.requested_species = right.to_owned(),
+                        _ => (),
+                    }
+                }
+            },
+        }
+    }
+
+    Ok(trades)
+}
+
+#[derive(Debug)]
+pub enum TradeError {
+    BracketParse(usize),
+    FieldParse(usize, String),
+}
+
+impl std::error::Error for TradeError {}
+
+impl std::fmt::Display for TradeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TradeError::BracketParse(line) => {
+                write!(
+                    f,
+                    "Could not parse trade id at line {} with bracket error",
+                    line
+                )
+            }
+            TradeError::FieldParse(line, text) => write!(
+                f,
+                "Could not parse trade field at line {} with text: {}",
+                line, text
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_trade_fields() {
+        let file = r#"
+static const struct InGameTrade sInGameTrades[] = {
+    [INGAME_TRADE_MR_MIME] = {
+        .nickname = _("MIMIEN"),
+        .species = SPECIES_MR_MIME,
+        .ivs = {20, 15, 17, 24, 23, 22},
+        .abilityNum = 0,
+        .otId = 1985,
+        .conditions = {5, 5, 5, 30, 5},
+        .personality = 0x00009cae,
+        .heldItem = ITEM_NONE,
+        .mailNum = 255,
+        .otName = _("REYLEY"),
+        .otGender = MALE,
+        .sheen = 10,
+        .requestedSpecies = SPECIES_ABRA
+    },
+};
+"#;
+        let trades = parse_trades(file).unwrap();
+        let trade = &trades["INGAME_TRADE_MR_MIME"];
+        assert_eq!(trade.nickname, "MIMIEN");
+        assert_eq!(trade.species, "SPECIES_MR_MIME");
+        assert_eq!(trade.held_item, "ITEM_NONE");
+        assert_eq!(trade.ot_name, "REYLEY");
+        assert_eq!(trade.requested_species, "SPECIES_ABRA");
+    }
+}
@@ -0,0 +1,747 @@
+//! Texture atlases of the metatiles of pret's tilesets.
+//!
+//! Each tileset is drawn into one atlas named after its [`PaletteId`], so the tiles
+//! of converted maps index into the atlases of their palettes.
+
+use std::{collections::BTreeMap, path::Path};
+
+use firecore_world_builder::world::map::{PaletteId, TileId};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use script_parser::anim::{TileAnimation, NUM_TILES_IN_PRIMARY};
+
+use crate::{
+    behavior::NUM_METATILES_IN_PRIMARY,
+    tileset::{tileset_dir, TilesetDirs, TILESET_PREFIX},
+    NameMappings, ParsedData, Source,
+};
+
+/// Source file of pret's tileset animations.
+pub const TILESET_ANIMS: &str = "src/tileset_anims.c";
+
+/// Width and height of a tile in pixels.
+pub const TILE_SIZE: usize = 8;
+
+/// Width and height of a metatile in pixels, which is two tiles by two tiles.
+pub const METATILE_SIZE: usize = TILE_SIZE * 2;
+
+/// Tiles drawn in each layer of a metatile.
+pub const METATILE_TILES: usize = 4;
+
+/// Colors in each of a tileset's palettes.
+pub const PALETTE_COLORS: usize = 16;
+
+/// Palette slots a tileset's tiles can use, of which pret writes one `.pal` file each.
+pub const PALETTES: usize = 16;
+
+/// Number of FireRed's palette slots taken from primary tilesets, after which secondary palettes start.
+pub const PRIMARY_PALETTES: usize = 7;
+
+/// First line of a JASC palette file.
+pub const JASC_HEADER: &str = "JASC-PAL";
+
+/// Red, green and blue of a palette color.
+pub type Color = [u8; 3];
+
+/// Metatiles in each row of an atlas.
+///
+/// Each metatile takes two cells of a row, its bottom layer and then its top layer.
+pub const ATLAS_COLUMNS: usize = 8;
+
+/// Pixels of a tileset's `tiles.png`, which are indices into one of its palettes.
+#[derive(Debug, Default, Clone)]
+pub struct TileSheet {
+    pub width: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl TileSheet {
+    /// Decodes an indexed PNG, such as pret's 4 bit `tiles.png`.
+    pub fn decode(png: &[u8]) -> anyhow::Result<Self> {
+        let mut decoder = png::Decoder::new(png);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let (width, height) = (info.width as usize, info.height as usize);
+        let mut pixels = Vec::with_capacity(width * height);
+        for line in buffer.chunks(info.line_size).take(height) {
+            match info.bit_depth {
+                png::BitDepth::Four => {
+                    pixels.extend((0..width).map(|x| (line[x / 2] >> ((1 - x % 2) * 4)) & 0xF))
+                }
+                png::BitDepth::Eight => pixels.extend_from_slice(&line[..width]),
+                depth => anyhow::bail!("Tiles with bit depth {:?} are not indexed", depth),
+            }
+        }
+        Ok(Self { width, pixels })
+    }
+
+    fn offset(&self, tile: usize, x: usize, y: usize) -> usize {
+        let columns = (self.width / TILE_SIZE).max(1);
+        let (column, row) = (tile % columns, tile / columns);
+        (row * TILE_SIZE + y) * self.width + column * TILE_SIZE + x
+    }
+
+    /// Gets a pixel of a tile, or `0` if the sheet has no such tile.
+    pub fn pixel(&self, tile: usize, x: usize, y: usize) -> u8 {
+        self.pixels
+            .get(self.offset(tile, x, y))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Copies the sheet with the tiles from `first` replaced by the first `tiles` tiles of an animation frame.
+    pub fn with_frame(&self, first: usize, frame: &TileSheet, tiles: usize) -> TileSheet {
+        let mut sheet = self.clone();
+        for tile in 0..tiles {
+            for y in 0..TILE_SIZE {
+                for x in 0..TILE_SIZE {
+                    let offset = sheet.offset(first + tile, x, y);
+                    if offset >= sheet.pixels.len() {
+                        sheet.pixels.resize(offset + 1, 0);
+                    }
+                    sheet.pixels[offset] = frame.pixel(tile, x, y);
+                }
+            }
+        }
+        sheet
+    }
+}
+
+/// Tile of a metatile, from pret's `metatiles.bin`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MetatileTile {
+    /// Index into the primary tiles, followed by the secondary tiles
+    pub tile: u16,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub palette: u8,
+}
+
+impl From<u16> for MetatileTile {
+    fn from(entry: u16) -> Self {
+        Self {
+            tile: entry & 0x3FF,
+            flip_x: entry & 0x400 != 0,
+            flip_y: entry & 0x800 != 0,
+            palette: (entry >> 12) as u8,
+        }
+    }
+}
+
+/// Bottom layer and then top layer of a metatile, each in rows from the top left.
+pub type Metatile = [MetatileTile; METATILE_TILES * 2];
+
+/// Reads the metatiles of a tileset's `metatiles.bin`.
+pub fn parse_metatiles(bytes: &[u8]) -> Vec<Metatile> {
+    bytes
+        .chunks_exact(METATILE_TILES * 2 * 2)
+        .map(|metatile| {
+            let mut tiles = Metatile::default();
+            for (tile, entry) in tiles.iter_mut().zip(metatile.chunks_exact(2)) {
+                *tile = u16::from_le_bytes([entry[0], entry[1]]).into();
+            }
+            tiles
+        })
+        .collect()
+}
+
+/// Lines up a secondary tileset's metatiles with the [`WorldTile::Secondary`] ids of converted maps,
+/// which count from the `primary_size` of their primary palette instead of from pret's
+/// [`NUM_METATILES_IN_PRIMARY`].
+///
+/// Primary metatiles numbered from `primary_size` come first, as converted maps use them as secondary tiles.
+///
+/// [`WorldTile::Secondary`]: firecore_world_builder::world::map::WorldTile::Secondary
+pub fn world_metatiles(
+    primary: &[Metatile],
+    secondary: &[Metatile],
+    primary_size: TileId,
+) -> Vec<Metatile> {
+    (primary_size..NUM_METATILES_IN_PRIMARY)
+        .map(|index| primary.get(index as usize).copied().unwrap_or_default())
+        .chain(
+            secondary
+                .iter()
+                .skip(primary_size.saturating_sub(NUM_METATILES_IN_PRIMARY) as usize)
+                .copied(),
+        )
+        .collect()
+}
+
+/// Reads the colors of a JASC palette file, such as pret's `palettes/00.pal`.
+pub fn parse_jasc(file: &str) -> Result<Vec<Color>, PaletteError> {
+    let mut lines = file.lines().map(str::trim).enumerate();
+    if lines.next().map(|(.., header)| header) != Some(JASC_HEADER) {
+        return Err(PaletteError::Header);
+    }
+    // version, then the number of colors
+    let (line, count) = lines.nth(1).ok_or(PaletteError::Header)?;
+    let count = count
+        .parse::<usize>()
+        .map_err(|_| PaletteError::Color(line, count.to_owned()))?;
+    lines
+        .filter(|(.., text)| !text.is_empty())
+        .take(count)
+        .map(|(line, text)| {
+            let mut channels = text.split_whitespace().map(str::parse::<u8>);
+            let mut channel = || {
+                channels
+                    .next()
+                    .and_then(Result::ok)
+                    .ok_or_else(|| PaletteError::Color(line, text.to_owned()))
+            };
+            Ok([channel()?, channel()?, channel()?])
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum PaletteError {
+    Header,
+    Color(usize, String),
+}
+
+impl std::error::Error for PaletteError {}
+
+impl std::fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaletteError::Header => write!(f, "Palette does not start with a JASC header"),
+            PaletteError::Color(line, text) => write!(
+                f,
+                "Could not parse palette color at line {} with text: {}",
+                line, text
+            ),
+        }
+    }
+}
+
+/// Reads the colors of every palette slot a tileset's tiles use, in slot order.
+///
+/// A secondary tileset takes its first [`PRIMARY_PALETTES`] slots from its primary tileset.
+/// Slots without a palette file are black.
+pub fn tileset_colors(
+    source: &dyn Source,
+    dirs: &TilesetDirs,
+    tileset: &str,
+    primary: Option<&str>,
+) -> anyhow::Result<Vec<Color>> {
+    let mut colors = Vec::with_capacity(PALETTES * PALETTE_COLORS);
+    for slot in 0..PALETTES {
+        let (tileset, is_primary) = match primary {
+            Some(primary) if slot < PRIMARY_PALETTES => (primary, true),
+            Some(..) => (tileset, false),
+            None => (tileset, true),
+        };
+        let dir = tileset_dir(dirs, tileset, is_primary)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a tileset symbol", tileset))?;
+        let mut palette = match source.text(&format!("{}/palettes/{:02}.pal", dir, slot)) {
+            Ok(file) => parse_jasc(&file)?,
+            Err(..) => Vec::new(),
+        };
+        palette.resize(PALETTE_COLORS, Color::default());
+        colors.extend(palette);
+    }
+    Ok(colors)
+}
+
+/// Gets the top left pixel of a metatile drawn by [`draw_atlas`].
+fn position(index: usize) -> (usize, usize) {
+    (
+        index % ATLAS_COLUMNS * 2 * METATILE_SIZE,
+        index / ATLAS_COLUMNS * METATILE_SIZE,
+    )
+}
+
+/// Metatiles drawn by [`draw_atlas`].
+#[derive(Debug, Default, Clone)]
+pub struct Atlas {
+    pub width: usize,
+    pub height: usize,
+    /// Each pixel is its palette times [`PALETTE_COLORS`] plus its color,
+    /// where the first color of every palette is transparent
+    pub pixels: Vec<u8>,
+    /// Number of metatiles drawn, not counting the frames of [animations](Atlas::animations)
+    pub metatiles: usize,
+    /// Animated metatiles, whose frames are drawn below the other metatiles
+    pub animations: Vec<AtlasAnimation>,
+}
+
+impl Atlas {
+    /// Gets where the layers of a metatile are drawn.
+    pub fn tile(&self, index: usize) -> AtlasTile {
+        let (left, top) = position(index);
+        AtlasTile {
+            bottom: [left as _, top as _],
+            top: [(left + METATILE_SIZE) as _, top as _],
+        }
+    }
+
+    /// Draws another atlas below this one, returning the index its first metatile is drawn at.
+    pub fn append(&mut self, atlas: Atlas) -> usize {
+        let first = self.height / METATILE_SIZE * ATLAS_COLUMNS;
+        self.height += atlas.height;
+        self.pixels.extend(atlas.pixels);
+        first
+    }
+}
+
+/// Metatiles whose tiles are replaced over time by a [`TileAnimation`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AtlasAnimation {
+    /// Name of the animation in pret, such as `Flower`
+    pub name: String,
+    /// Game frames between animation frames
+    pub interval: usize,
+    /// Where each frame of an animated metatile is drawn, keyed by its tile id
+    pub tiles: BTreeMap<usize, Vec<AtlasTile>>,
+}
+
+/// Draws metatiles into an atlas of [`ATLAS_COLUMNS`] metatiles per row.
+pub fn draw_atlas(
+    metatiles: &[Metatile],
+    primary: &TileSheet,
+    secondary: Option<&TileSheet>,
+) -> Atlas {
+    let width = ATLAS_COLUMNS * 2 * METATILE_SIZE;
+    let height = metatiles.len().div_ceil(ATLAS_COLUMNS).max(1) * METATILE_SIZE;
+    let mut pixels = vec![0; width * height];
+
+    for (index, metatile) in metatiles.iter().enumerate() {
+        let (left, top) = position(index);
+        for (position, tile) in metatile.iter().enumerate() {
+            let (layer, quarter) = (position / METATILE_TILES, position % METATILE_TILES);
+            let left = left + layer * METATILE_SIZE + quarter % 2 * TILE_SIZE;
+            let top = top + quarter / 2 * TILE_SIZE;
+            let (sheet, number) = match (tile.tile as usize).checked_sub(NUM_TILES_IN_PRIMARY) {
+                Some(number) => match secondary {
+                    Some(secondary) => (secondary, number),
+                    None => continue,
+                },
+                None => (primary, tile.tile as usize),
+            };
+            for y in 0..TILE_SIZE {
+                for x in 0..TILE_SIZE {
+                    let color = sheet.pixel(
+                        number,
+                        if tile.flip_x { TILE_SIZE - 1 - x } else { x },
+                        if tile.flip_y { TILE_SIZE - 1 - y } else { y },
+                    );
+                    pixels[(top + y) * width + left + x] =
+                        tile.palette * PALETTE_COLORS as u8 + color;
+                }
+            }
+        }
+    }
+
+    Atlas {
+        width,
+        height,
+        pixels,
+        metatiles: metatiles.len(),
+        animations: Vec::new(),
+    }
+}
+
+/// Encodes an atlas drawn by [`draw_atlas`] as an indexed PNG with the colors of
+/// [`tileset_colors`] baked in, keeping the first color of every palette transparent.
+pub fn encode_atlas(atlas: &Atlas, colors: &[Color]) -> anyhow::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png, atlas.width as _, atlas.height as _);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(colors.iter().flatten().copied().collect::<Vec<_>>());
+        encoder.set_trns(
+            (0..colors.len())
+                .map(|color| match color % PALETTE_COLORS {
+                    0 => 0,
+                    _ => u8::MAX,
+                })
+                .collect::<Vec<_>>(),
+        );
+        encoder.write_header()?.write_image_data(&atlas.pixels)?;
+    }
+    Ok(png)
+}
+
+/// Tileset drawn by [`tileset_atlases`].
+pub struct TilesetAtlas {
+    pub tileset: String,
+    pub palette: PaletteId,
+    pub atlas: Atlas,
+    /// Colors of the atlas's pixels, from [`tileset_colors`]
+    pub colors: Vec<Color>,
+}
+
+impl TilesetAtlas {
+    /// Lists where each tile of the tileset is drawn.
+    pub fn index(&self) -> AtlasIndex {
+        AtlasIndex {
+            tileset: self.tileset.clone(),
+            palette: self.palette,
+            animations: self.atlas.animations.clone(),
+            size: METATILE_SIZE as _,
+            tiles: (0..self.atlas.metatiles)
+                .map(|index| self.atlas.tile(index))
+                .collect(),
+        }
+    }
+}
+
+/// Where the tiles of a tileset are drawn in its atlas, so renderers
+/// can draw maps without composing pret's metatiles themselves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlasIndex {
+    pub tileset: String,
+    pub palette: PaletteId,
+    /// Animated tiles, from pret's [tileset animations](TILESET_ANIMS)
+    pub animations: Vec<AtlasAnimation>,
+    /// Width and height of each layer of a tile in pixels
+    pub size: u32,
+    /// Indexed by the id of a [`WorldTile`] in the maps using the tileset,
+    /// which for secondary tilesets is ordered by [`world_metatiles`]
+    ///
+    /// [`WorldTile`]: firecore_world_builder::world::map::WorldTile
+    pub tiles: Vec<AtlasTile>,
+}
+
+/// Top left pixels of the layers of a tile in its atlas.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasTile {
+    /// Layer drawn below NPCs and the player
+    pub bottom: [u32; 2],
+    /// Layer drawn above NPCs and the player, where the first color of each palette is transparent
+    pub top: [u32; 2],
+}
+
+/// Draws a tileset, along with the primary tileset it uses the tiles of if it is a secondary tileset.
+///
+/// Secondary tilesets are drawn in the order of [`world_metatiles`], given the
+/// `primary_size` of the primary tileset's palette.
+/// The frames of `animations` are drawn below the tileset's metatiles.
+/// Animations whose frames cannot be read are left out.
+pub fn draw_tileset(
+    source: &dyn Source,
+    dirs: &TilesetDirs,
+    tileset: &str,
+    primary: Option<(&str, TileId)>,
+    animations: &[&TileAnimation],
+) -> anyhow::Result<Atlas> {
+    let dir = |tileset: &str, primary: bool| {
+        tileset_dir(dirs, tileset, primary)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a tileset symbol", tileset))
+    };
+    let sheet = |tileset: &str, primary: bool| -> anyhow::Result<TileSheet> {
+        TileSheet::decode(&source.get(&format!("{}/tiles.png", dir(tileset, primary)?))?)
+    };
+    let metatiles = |tileset: &str, primary: bool| -> anyhow::Result<Vec<Metatile>> {
+        Ok(parse_metatiles(&source.get(&format!(
+            "{}/metatiles.bin",
+            dir(tileset, primary)?
+        ))?))
+    };
+    let (metatiles, sheets) = match primary {
+        Some((primary, primary_size)) => (
+            world_metatiles(
+                &metatiles(primary, true)?,
+                &metatiles(tileset, false)?,
+                primary_size,
+            ),
+            (sheet(primary, true)?, Some(sheet(tileset, false)?)),
+        ),
+        None => (metatiles(tileset, true)?, (sheet(tileset, true)?, None)),
+    };
+
+    let mut atlas = draw_atlas(&metatiles, &sheets.0, sheets.1.as_ref());
+
+    for animation in animations {
+        if let Err(err) = draw_animation(source, &mut atlas, &metatiles, &sheets, animation) {
+            eprintln!(
+                "Could not draw animation {} of {} with error {}",
+                animation.name, tileset, err
+            );
+        }
+    }
+
+    Ok(atlas)
+}
+
+/// Draws every frame of the metatiles an animation changes below an atlas.
+fn draw_animation(
+    source: &dyn Source,
+    atlas: &mut Atlas,
+    metatiles: &[Metatile],
+    (primary, secondary): &(TileSheet, Option<TileSheet>),
+    animation: &TileAnimation,
+) -> anyhow::Result<()> {
+    let range = animation.tile..animation.tile + animation.tiles;
+    let (indices, animated): (Vec<_>, Vec<_>) = metatiles
+        .iter()
+        .enumerate()
+        .filter(|(.., metatile)| {
+            metatile
+                .iter()
+                .any(|tile| range.contains(&(tile.tile as usize)))
+        })
+        .map(|(index, metatile)| (index, *metatile))
+        .unzip();
+
+    if animated.is_empty() {
+        return Ok(());
+    }
+
+    let mut tiles = BTreeMap::<usize, Vec<AtlasTile>>::new();
+
+    for frame in animation.frames.iter() {
+        let frame = TileSheet::decode(&source.get(frame)?)?;
+        let drawn = match animation.tile.checked_sub(NUM_TILES_IN_PRIMARY) {
+            Some(first) => {
+                let secondary = secondary
+                    .as_ref()
+                    .map(|secondary| secondary.with_frame(first, &frame, animation.tiles));
+                draw_atlas(&animated, primary, secondary.as_ref())
+            }
+            None => draw_atlas(
+                &animated,
+                &primary.with_frame(animation.tile, &frame, animation.tiles),
+                secondary.as_ref(),
+            ),
+        };
+        let first = atlas.append(drawn);
+        for (slot, index) in indices.iter().enumerate() {
+            tiles
+                .entry(*index)
+                .or_default()
+                .push(atlas.tile(first + slot));
+        }
+    }
+
+    atlas.animations.push(AtlasAnimation {
+        name: animation.name.clone(),
+        interval: animation.interval,
+        tiles,
+    });
+
+    Ok(())
+}
+
+/// Draws the atlas of every tileset with a palette mapping.
+///
+/// Secondary tilesets are drawn with the first primary tileset, by name, that a layout pairs them with,
+/// and are animated by the animations of both.
+/// Tilesets whose files cannot be read, or whose primary palette has no size mapping, are left out.
+pub fn tileset_atlases(
+    source: &dyn Source,
+    mappings: &NameMappings,
+    data: &ParsedData,
+) -> Vec<TilesetAtlas> {
+    let animations = match source.text(TILESET_ANIMS) {
+        Ok(file) => script_parser::anim::parse_tileset_anims(&file),
+        Err(err) => {
+            eprintln!("Could not get tileset animations with error {}", err);
+            Vec::new()
+        }
+    };
+
+    let mut tilesets = BTreeMap::<String, Option<String>>::new();
+    for map in data.maps.iter() {
+        let layout = &map.value().layout;
+        tilesets
+            .entry(layout.primary_tileset.clone())
+            .or_insert(None);
+        let paired = tilesets
+            .entry(layout.secondary_tileset.clone())
+            .or_insert_with(|| Some(layout.primary_tileset.clone()));
+        if let Some(paired) = paired {
+            if layout.primary_tileset < *paired {
+                *paired = layout.primary_tileset.clone();
+            }
+        }
+    }
+
+    tilesets
+        .into_iter()
+        .flat_map(|(tileset, primary)| {
+            let palette = match primary {
+                Some(..) => mappings.palettes.secondary.get(&tileset),
+                None => mappings.palettes.primary.get(&tileset),
+            };
+            let primary = match primary {
+                Some(primary) => {
+                    let size = mappings
+                        .palettes
+                        .primary
+                        .get(&primary)
+                        .and_then(|palette| mappings.palettes.sizes.get(palette));
+                    match size {
+                        Some(size) => Some((primary, *size)),
+                        None => {
+                            eprintln!(
+                                "Cannot get the size of primary tileset {} to draw {}",
+                                primary, tileset
+                            );
+                            return None;
+                        }
+                    }
+                }
+                None => None,
+            };
+            Some((tileset, primary, *palette?))
+        })
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .flat_map(|(tileset, primary, palette)| {
+            let animations = animations
+                .iter()
+                .filter(|animation| {
+                    let symbol = format!("{}{}", TILESET_PREFIX, animation.tileset);
+                    symbol == tileset
+                        || Some(symbol.as_str()) == primary.as_ref().map(|(p, ..)| p.as_str())
+                })
+                .collect::<Vec<_>>();
+            let primary = primary
+                .as_ref()
+                .map(|(primary, size)| (primary.as_str(), *size));
+            let dirs = &data.tileset_dirs;
+            let drawn =
+                draw_tileset(source, dirs, &tileset, primary, &animations).and_then(|drawn| {
+                    Ok((
+                        drawn,
+                        tileset_colors(source, dirs, &tileset, primary.map(|(p, ..)| p))?,
+                    ))
+                });
+            match drawn {
+                Ok((atlas, colors)) => Some(TilesetAtlas {
+                    tileset,
+                    palette,
+                    atlas,
+                    colors,
+                }),
+                Err(err) => {
+                    eprintln!("Could not draw tileset {} with error {}", tileset, err);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Writes each atlas to `<dir>/<palette>.png` and its [index](AtlasIndex) to `<dir>/<palette>.json`,
+/// returning how many were written.
+pub fn write_atlases(dir: &Path, atlases: &[TilesetAtlas]) -> anyhow::Result<usize> {
+    std::fs::create_dir_all(dir)?;
+    for atlas in atlases {
+        std::fs::write(
+            dir.join(format!("{}.png", atlas.palette)),
+            encode_atlas(&atlas.atlas, &atlas.colors)?,
+        )?;
+        std::fs::write(
+            dir.join(format!("{}.json", atlas.palette)),
+            serde_json::to_string_pretty(&atlas.index())?,
+        )?;
+    }
+    Ok(atlases.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use firecore_world_builder::world::map::WorldTile;
+
+    use super::*;
+
+    fn metatile(tile: u16, palette: u8) -> Metatile {
+        [MetatileTile {
+            tile,
+            palette,
+            ..Default::default()
+        }; METATILE_TILES * 2]
+    }
+
+    fn sheet(color: u8) -> TileSheet {
+        TileSheet {
+            width: TILE_SIZE,
+            pixels: vec![color; TILE_SIZE * TILE_SIZE],
+        }
+    }
+
+    #[test]
+    fn converted_secondary_tile_indexes_its_metatile() {
+        let primary = vec![metatile(0, 0); NUM_METATILES_IN_PRIMARY as usize];
+        let secondary = [
+            metatile(NUM_TILES_IN_PRIMARY as u16, 7),
+            metatile(NUM_TILES_IN_PRIMARY as u16, 8),
+        ];
+
+        // the second secondary metatile, as pret's blockdata numbers it
+        let tile = match crate::convert::into_world_tile(576, NUM_METATILES_IN_PRIMARY + 1) {
+            WorldTile::Secondary(tile) => tile,
+            _ => panic!("Secondary metatiles should convert into secondary tiles"),
+        };
+        assert_eq!(tile, 65);
+
+        let atlas = TilesetAtlas {
+            tileset: "gTileset_PalletTown".to_owned(),
+            palette: 13,
+            atlas: draw_atlas(
+                &world_metatiles(&primary, &secondary, 576),
+                &sheet(1),
+                Some(&sheet(5)),
+            ),
+            colors: Vec::new(),
+        };
+        let [x, y] = atlas.index().tiles[tile as usize].bottom;
+        let pixel = atlas.atlas.pixels[y as usize * atlas.atlas.width + x as usize];
+        assert_eq!(pixel, 8 * PALETTE_COLORS as u8 + 5);
+    }
+
+    #[test]
+    fn parses_jasc_palettes() {
+        let file = "JASC-PAL\r\n0100\r\n3\r\n255 0 255\r\n0 0 0\r\n246 246 246\r\n";
+        let colors = parse_jasc(file).unwrap();
+        assert_eq!(colors, [[255, 0, 255], [0, 0, 0], [246, 246, 246]]);
+
+        assert!(matches!(
+            parse_jasc("GIMP Palette"),
+            Err(PaletteError::Header)
+        ));
+        assert!(matches!(
+            parse_jasc("JASC-PAL\n0100\n1\n255 0\n"),
+            Err(PaletteError::Color(3, text)) if text == "255 0"
+        ));
+    }
+
+    #[test]
+    fn parses_metatile_entries() {
+        let entries = [0x3001u16, 0x0C02, 0, 0, 0, 0, 0, 0];
+        let mut bytes = entries
+            .iter()
+            .flat_map(|entry| entry.to_le_bytes())
+            .collect::<Vec<_>>();
+        // a trailing partial metatile is left out
+        bytes.extend([1, 2]);
+
+        let metatiles = parse_metatiles(&bytes);
+        assert_eq!(metatiles.len(), 1);
+        assert_eq!(
+            metatiles[0][0],
+            MetatileTile {
+                tile: 1,
+                palette: 3,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            metatiles[0][1],
+            MetatileTile {
+                tile: 2,
+                flip_x: true,
+                flip_y: true,
+                palette: 0,
+            }
+        );
+    }
+}
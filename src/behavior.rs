@@ -0,0 +1,237 @@
+//! Metatile behaviors from pret's tileset attributes.
+
+use firecore_world_builder::world::{
+    map::{movement::MovementId, TileId},
+    positions::Coordinate,
+};
+use hashbrown::{HashMap, HashSet};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use crate::{map::JsonMapLayout, tileset::TilesetDirs, Source};
+
+/// Header defining pret's metatile behavior constants.
+pub const BEHAVIORS_HEADER: &str = "include/constants/metatile_behaviors.h";
+
+/// Prefix of pret's metatile behavior constants.
+pub const BEHAVIOR_PREFIX: &str = "MB_";
+
+/// Bits of a FireRed metatile attribute that hold its behavior.
+pub const BEHAVIOR_MASK: u32 = 0x1FF;
+
+pub type Behavior = u16;
+
+/// Number of metatiles in FireRed's primary tilesets, which secondary metatiles are numbered after.
+pub const NUM_METATILES_IN_PRIMARY: TileId = 640;
+
+/// Bits of a movement that block walking onto its tile, as in pret's blockdata collision.
+pub const COLLISION_MASK: MovementId = 0b11;
+
+/// Bits of a movement above its collision that hold the tile's elevation.
+pub const ELEVATION_SHIFT: u8 = 2;
+
+/// Elevation of water surfaces, which can only be crossed by surfing.
+pub const ELEVATION_SURF: MovementId = 1;
+
+/// Elevation of most ground in pret's maps.
+pub const ELEVATION_GROUND: MovementId = 3;
+
+/// How a behavior decides whether its tiles can be crossed, whatever their collision says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Passability {
+    /// Nothing can move onto the tile
+    Impassable,
+    /// The tile can be walked on, even when it is drawn as water
+    Walkable,
+    /// The tile can only be crossed by surfing
+    Surfable,
+}
+
+/// Behaviors of the metatiles of every tileset used by a layout.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TilesetBehaviors {
+    /// Values of behavior constants, such as `MB_TALL_GRASS`
+    pub names: HashMap<String, Behavior>,
+    /// Behavior of each metatile, keyed by tileset symbol
+    pub tilesets: HashMap<String, Vec<Behavior>>,
+}
+
+impl TilesetBehaviors {
+    /// Reads the behavior constants and the `metatile_attributes.bin` of each tileset.
+    ///
+    /// Tilesets whose attributes cannot be read are left out.
+    pub fn load<'a>(
+        source: &dyn Source,
+        dirs: &TilesetDirs,
+        layouts: impl Iterator<Item = &'a JsonMapLayout>,
+    ) -> anyhow::Result<Self> {
+        let names = parse_behaviors(&source.text(BEHAVIORS_HEADER)?);
+
+        let mut tilesets = HashSet::new();
+        for layout in layouts {
+            tilesets.insert((layout.primary_tileset.clone(), true));
+            tilesets.insert((layout.secondary_tileset.clone(), false));
+        }
+
+        let tilesets = tilesets
+            .into_par_iter()
+            .flat_map(|(tileset, primary)| {
+                let dir = crate::tileset::tileset_dir(dirs, &tileset, primary)?;
+                match source.get(&format!("{}/metatile_attributes.bin", dir)) {
+                    Ok(attributes) => Some((
+                        tileset,
+                        attributes
+                            .chunks_exact(4)
+                            .map(|attribute| {
+                                (u32::from_le_bytes([
+                                    attribute[0],
+                                    attribute[1],
+                                    attribute[2],
+                                    attribute[3],
+                                ]) & BEHAVIOR_MASK) as Behavior
+                            })
+                            .collect(),
+                    )),
+                    Err(err) => {
+                        eprintln!(
+                            "Could not get metatile attributes of {} with error {}",
+                            tileset, err
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Ok(Self { names, tilesets })
+    }
+
+    /// Gets the behavior of each of a map's pret metatiles in row order,
+    /// where metatiles below [`NUM_METATILES_IN_PRIMARY`] are in the primary tileset.
+    ///
+    /// Tiles of unknown tilesets have behavior `0`, which is `MB_NORMAL`.
+    pub fn map(&self, layout: &JsonMapLayout, tiles: &[TileId]) -> Vec<Behavior> {
+        let primary = self.tilesets.get(&layout.primary_tileset);
+        let secondary = self.tilesets.get(&layout.secondary_tileset);
+        tiles
+            .iter()
+            .map(|tile| {
+                match NUM_METATILES_IN_PRIMARY > *tile {
+                    true => primary.and_then(|b| b.get(*tile as usize)),
+                    false => {
+                        secondary.and_then(|b| b.get((*tile - NUM_METATILES_IN_PRIMARY) as usize))
+                    }
+                }
+                .copied()
+                .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Gets the values of behavior constants, ignoring unknown names.
+    pub fn values<'a>(&self, names: impl IntoIterator<Item = &'a String>) -> HashSet<Behavior> {
+        names
+            .into_iter()
+            .flat_map(|name| self.names.get(name))
+            .copied()
+            .collect()
+    }
+
+    /// Keys tags of behavior constants by their values, ignoring unknown names.
+    pub fn keyed<T: Copy>(&self, tags: &HashMap<String, T>) -> HashMap<Behavior, T> {
+        tags.iter()
+            .flat_map(|(name, tag)| Some((*self.names.get(name)?, *tag)))
+            .collect()
+    }
+
+    /// Groups the tiles of a map by the tag of their behavior,
+    /// such as a [`TerrainSound`](crate::TerrainSound).
+    pub fn tag<T: Copy + Eq + std::hash::Hash>(
+        &self,
+        behaviors: &[Behavior],
+        width: usize,
+        tags: &HashMap<String, T>,
+    ) -> HashMap<T, Vec<Coordinate>> {
+        let tags = self.keyed(tags);
+        let mut tagged = HashMap::<T, Vec<Coordinate>>::new();
+        for (index, behavior) in behaviors.iter().enumerate() {
+            if let Some(tag) = tags.get(behavior) {
+                tagged.entry(*tag).or_default().push(Coordinate {
+                    x: (index % width) as _,
+                    y: (index / width) as _,
+                });
+            }
+        }
+        tagged
+    }
+}
+
+/// Changes the movements of tiles whose behavior has a [`Passability`],
+/// as pret often leaves the collision and elevation of such tiles to their behavior.
+pub fn refine_movements(
+    movements: &mut [MovementId],
+    behaviors: &[Behavior],
+    passability: &HashMap<Behavior, Passability>,
+) {
+    for (movement, behavior) in movements.iter_mut().zip(behaviors) {
+        let elevation = *movement >> ELEVATION_SHIFT;
+        *movement = match passability.get(behavior) {
+            Some(Passability::Impassable) => *movement | COLLISION_MASK,
+            Some(Passability::Walkable) => match elevation == ELEVATION_SURF {
+                true => ELEVATION_GROUND << ELEVATION_SHIFT,
+                false => *movement & !COLLISION_MASK,
+            },
+            Some(Passability::Surfable) => ELEVATION_SURF << ELEVATION_SHIFT,
+            None => continue,
+        };
+    }
+}
+
+/// Finds the tiles of a map with one of the behaviors, sorted by row.
+pub fn coordinates(
+    behaviors: &[Behavior],
+    width: usize,
+    set: &HashSet<Behavior>,
+) -> Vec<Coordinate> {
+    behaviors
+        .iter()
+        .enumerate()
+        .filter(|(.., behavior)| set.contains(*behavior))
+        .map(|(index, ..)| Coordinate {
+            x: (index % width) as _,
+            y: (index / width) as _,
+        })
+        .collect()
+}
+
+/// Gets the behavior constants of the header, which are either `#define`s
+/// or the members of an enum counting up from `MB_NORMAL`.
+pub fn parse_behaviors(header: &str) -> HashMap<String, Behavior> {
+    let mut behaviors = crate::registry::parse_defines(header);
+    behaviors.retain(|name, ..| name.starts_with(BEHAVIOR_PREFIX));
+    if !behaviors.is_empty() {
+        return behaviors;
+    }
+    let mut next = 0;
+    for line in header.lines().map(str::trim) {
+        if !line.starts_with(BEHAVIOR_PREFIX) {
+            continue;
+        }
+        let line = line.split("//").next().unwrap_or_default();
+        let line = line.trim().trim_end_matches(',');
+        let (name, value) = match line.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (line, ""),
+        };
+        if let Some(value) = value.strip_prefix("0x") {
+            if let Ok(value) = Behavior::from_str_radix(value, 16) {
+                next = value;
+            }
+        } else if let Ok(value) = value.parse() {
+            next = value;
+        }
+        behaviors.insert(name.to_owned(), next);
+        next += 1;
+    }
+    behaviors
+}
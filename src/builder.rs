@@ -0,0 +1,223 @@
+//! Checked construction of [`WorldMap`]s, for the converter and for hand-written or procedural maps.
+
+use firecore_world_builder::world::{
+    character::npc::Npcs,
+    map::{
+        chunk::WorldChunk,
+        movement::MovementId,
+        object::{Items, Objects, Signs},
+        warp::WarpEntry,
+        wild::{WildEntry, WildType},
+        Brightness, PaletteId, WorldMap, WorldMapSettings, WorldTile,
+    },
+    positions::{Coordinate, CoordinateInt, Location},
+};
+use hashbrown::HashMap;
+use tinystr::TinyStr16;
+
+/// Builds a [`WorldMap`], checking that its tiles fill the map and that
+/// everything placed on it is inside its bounds.
+///
+/// ```no_run
+/// # fn run() -> Result<(), firecore_world_gen::builder::BuildError> {
+/// use firecore_world_gen::builder::WorldMapBuilder;
+/// use firecore_world_builder::world::positions::Location;
+///
+/// let id = Location {
+///     map: None,
+///     index: "pallet".parse().unwrap(),
+/// };
+///
+/// let map = WorldMapBuilder::new(id, "Pallet Town", 24, 20)
+///     .music("pallet".parse().unwrap())
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct WorldMapBuilder {
+    map: WorldMap,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The map has no tiles
+    Empty,
+    /// The number of tiles or movements does not match the map's dimensions
+    Size {
+        expected: usize,
+        tiles: usize,
+        movements: usize,
+    },
+    /// Something is placed outside of the map
+    OutOfBounds { what: String, coords: Coordinate },
+}
+
+impl std::error::Error for BuildError {}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::Empty => write!(f, "Map has no width or height"),
+            BuildError::Size {
+                expected,
+                tiles,
+                movements,
+            } => write!(
+                f,
+                "Map should have {} tiles and movements but has {} tiles and {} movements",
+                expected, tiles, movements
+            ),
+            BuildError::OutOfBounds { what, coords } => write!(
+                f,
+                "{} at {}, {} is outside of the map",
+                what, coords.x, coords.y
+            ),
+        }
+    }
+}
+
+impl WorldMapBuilder {
+    /// Starts a map filled with the first primary tile, with no movement restrictions.
+    pub fn new(
+        id: Location,
+        name: impl Into<String>,
+        width: CoordinateInt,
+        height: CoordinateInt,
+    ) -> Self {
+        let size = (width.max(0) * height.max(0)) as usize;
+        Self {
+            map: WorldMap {
+                id,
+                name: name.into(),
+                music: "pallet".parse().unwrap(),
+                width,
+                height,
+                tiles: vec![WorldTile::Primary(0); size],
+                palettes: [0, 0],
+                movements: vec![0; size],
+                border: [WorldTile::Primary(0); 4],
+                chunk: None,
+                warps: Vec::new(),
+                wild: None,
+                npcs: Default::default(),
+                objects: Default::default(),
+                items: Default::default(),
+                signs: Default::default(),
+                settings: WorldMapSettings {
+                    fly_position: None,
+                    brightness: Brightness::Day,
+                    transition: WorldMapSettings::default_transition(),
+                },
+            },
+        }
+    }
+
+    pub fn music(mut self, music: TinyStr16) -> Self {
+        self.map.music = music;
+        self
+    }
+
+    /// Sets the tiles and movements of the map, in rows from the top left.
+    pub fn tiles(mut self, tiles: Vec<WorldTile>, movements: Vec<MovementId>) -> Self {
+        self.map.tiles = tiles;
+        self.map.movements = movements;
+        self
+    }
+
+    pub fn palettes(mut self, palettes: [PaletteId; 2]) -> Self {
+        self.map.palettes = palettes;
+        self
+    }
+
+    pub fn border(mut self, border: [WorldTile; 4]) -> Self {
+        self.map.border = border;
+        self
+    }
+
+    pub fn chunk(mut self, chunk: Option<WorldChunk>) -> Self {
+        self.map.chunk = chunk;
+        self
+    }
+
+    pub fn warps(mut self, warps: Vec<WarpEntry>) -> Self {
+        self.map.warps = warps;
+        self
+    }
+
+    /// Sets the wild encounters of the map, leaving it without any if `wild` is empty.
+    pub fn wild(mut self, wild: HashMap<WildType, WildEntry>) -> Self {
+        self.map.wild = (!wild.is_empty()).then_some(wild);
+        self
+    }
+
+    pub fn npcs(mut self, npcs: Npcs) -> Self {
+        self.map.npcs = npcs;
+        self
+    }
+
+    pub fn objects(mut self, objects: Objects) -> Self {
+        self.map.objects = objects;
+        self
+    }
+
+    pub fn items(mut self, items: Items) -> Self {
+        self.map.items = items;
+        self
+    }
+
+    pub fn signs(mut self, signs: Signs) -> Self {
+        self.map.signs = signs;
+        self
+    }
+
+    pub fn settings(mut self, settings: WorldMapSettings) -> Self {
+        self.map.settings = settings;
+        self
+    }
+
+    pub fn build(self) -> Result<WorldMap, BuildError> {
+        let map = self.map;
+
+        if map.width <= 0 || map.height <= 0 {
+            return Err(BuildError::Empty);
+        }
+
+        let expected = (map.width * map.height) as usize;
+
+        if map.tiles.len() != expected || map.movements.len() != expected {
+            return Err(BuildError::Size {
+                expected,
+                tiles: map.tiles.len(),
+                movements: map.movements.len(),
+            });
+        }
+
+        let check = |what: String, coords: Coordinate| match coords.x >= 0
+            && coords.y >= 0
+            && coords.x < map.width
+            && coords.y < map.height
+        {
+            true => Ok(()),
+            false => Err(BuildError::OutOfBounds { what, coords }),
+        };
+
+        for warp in map.warps.iter() {
+            check("Warp".to_owned(), warp.area.min)?;
+            check("Warp".to_owned(), warp.area.max)?;
+        }
+        for (id, npc) in map.npcs.iter() {
+            check(format!("NPC {}", id), npc.character.position.coords)?;
+        }
+        for coords in map.objects.keys() {
+            check("Object".to_owned(), *coords)?;
+        }
+        for coords in map.items.keys() {
+            check("Item".to_owned(), *coords)?;
+        }
+        for coords in map.signs.keys() {
+            check("Sign".to_owned(), *coords)?;
+        }
+
+        Ok(map)
+    }
+}
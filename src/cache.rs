@@ -0,0 +1,132 @@
+//! Parsed pret data kept between runs, safe to share between concurrent runs.
+
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Name of the index file in the cache directory.
+pub const INDEX_FILE: &str = "cache.json";
+
+const LOCK_FILE: &str = "cache.lock";
+
+/// Locks older than this are assumed to be left over from a run that crashed.
+const STALE_LOCK: Duration = Duration::from_secs(60 * 60);
+
+const LOCK_RETRY: Duration = Duration::from_millis(250);
+
+/// Files in the cache, keyed by what they were made from.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheIndex {
+    pub entries: BTreeMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// File name in the cache directory
+    pub file: String,
+    /// CRC32 of the file, to detect partial or corrupted files
+    pub checksum: u32,
+}
+
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// Held while using the cache; other runs wait until it is dropped.
+pub struct CacheLock {
+    path: PathBuf,
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            eprintln!("Could not remove cache lock with error {}", err);
+        }
+    }
+}
+
+impl Cache {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_owned(),
+        }
+    }
+
+    /// Waits until no other run is using the cache.
+    pub fn lock(&self) -> anyhow::Result<CacheLock> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(LOCK_FILE);
+        let mut waiting = false;
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", std::process::id())?;
+                    return Ok(CacheLock { path });
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let age = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+                    if age.map(|age| age > STALE_LOCK).unwrap_or_default() {
+                        eprintln!("Removing stale cache lock {:?}", path);
+                        std::fs::remove_file(&path).ok();
+                        continue;
+                    }
+                    if !waiting {
+                        println!("Waiting for another run to release the cache...");
+                        waiting = true;
+                    }
+                    std::thread::sleep(LOCK_RETRY);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    pub fn index(&self) -> CacheIndex {
+        std::fs::read(self.dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|index| serde_json::from_slice(&index).ok())
+            .unwrap_or_default()
+    }
+
+    /// Gets a cached file, if it exists and is intact.
+    pub fn get(&self, _lock: &CacheLock, key: &str) -> Option<Vec<u8>> {
+        let entry = self.index().entries.remove(key)?;
+        let bytes = std::fs::read(self.dir.join(&entry.file)).ok()?;
+        (crc32fast::hash(&bytes) == entry.checksum).then_some(bytes)
+    }
+
+    /// Stores a file and adds it to the index, writing both atomically.
+    pub fn put(&self, _lock: &CacheLock, key: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        let file = format!("{:08x}.bin", crc32fast::hash(key.as_bytes()));
+        write_atomic(&self.dir.join(&file), bytes)?;
+        let mut index = self.index();
+        index.entries.insert(
+            key.to_owned(),
+            CacheEntry {
+                file,
+                checksum: crc32fast::hash(bytes),
+            },
+        );
+        write_atomic(
+            &self.dir.join(INDEX_FILE),
+            &serde_json::to_vec_pretty(&index)?,
+        )
+    }
+}
+
+/// Writes to a temporary file and renames it over `path`, so readers never see a partial file.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&temporary, bytes)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
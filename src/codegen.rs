@@ -0,0 +1,58 @@
+//! Rust constants for engine code, so maps and flags are checked at compile time.
+//!
+//! The generated files are meant to be `include!`d in a module that has
+//! `Location` and the `tinystr` crate in scope.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use firecore_world_builder::world::positions::Location;
+
+use crate::registry::Registry;
+
+const HEADER: &str = "// Generated by firecore-world-gen. Do not edit.\n";
+
+/// Turns a pret constant into a Rust constant name, such as `PALLET_TOWN` for `MAP_PALLET_TOWN`.
+fn constant(prefix: &str, name: &str) -> String {
+    let name = name.strip_prefix(prefix).unwrap_or(name);
+    let name = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect::<String>();
+    match name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", name),
+        false => name,
+    }
+}
+
+/// Writes a `Location` constant for every map, keyed by pret map id.
+pub fn locations(maps: &BTreeMap<String, Location>) -> String {
+    let mut file = String::from(HEADER);
+    for (id, location) in maps.iter() {
+        let map = match &location.map {
+            Some(map) => format!("Some(tinystr::tinystr!(16, {:?}))", map.as_str()),
+            None => "None".to_owned(),
+        };
+        writeln!(
+            file,
+            "\n/// `{}`\npub const {}: Location = Location {{\n    map: {},\n    index: tinystr::tinystr!(16, {:?}),\n}};",
+            id,
+            constant("MAP_", id),
+            map,
+            location.index.as_str()
+        )
+        .unwrap();
+    }
+    file
+}
+
+/// Writes a `u16` constant for every flag and variable in the registry.
+pub fn flags(registry: &Registry) -> String {
+    let mut file = String::from(HEADER);
+    for (name, id) in registry.flags.iter().chain(registry.vars.iter()) {
+        writeln!(file, "pub const {}: u16 = {:#06x};", constant("", name), id).unwrap();
+    }
+    file
+}
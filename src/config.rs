@@ -0,0 +1,325 @@
+//! Options read from `world-gen.toml`, the environment and the command line.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use hashbrown::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    converter::{ConverterBuilder, Game, Profile, Spawn},
+    registry::RegistryConfig,
+    source::{self, HttpSource},
+    Source,
+};
+
+/// Name of the config file looked for in the working directory.
+pub const CONFIG_FILE: &str = "world-gen.toml";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Local directory or zip archive of pret's repository, used instead of downloading
+    pub source: Option<PathBuf>,
+    /// Branch, tag or commit of pret's repository to download from
+    #[serde(rename = "ref")]
+    pub reference: String,
+    pub game: Game,
+    /// Fail if the conversion reports any diagnostics
+    pub strict: bool,
+    /// Match map names without a mapping to the closest mapped name
+    pub fuzzy: bool,
+    /// Prefixes of pret map ids to convert, or every map if empty
+    pub filter: Vec<String>,
+    /// Project mappings layered over the built-in mappings
+    pub mappings: PathBuf,
+    /// Personal mappings layered over the project mappings
+    pub user_mappings: Option<PathBuf>,
+    /// Edits applied to the converted maps
+    pub edits: PathBuf,
+    pub output: OutputConfig,
+    /// Where parsed pret data is kept between runs
+    pub cache: PathBuf,
+    /// Match tilesets without a palette mapping against known palettes by hashing their files
+    pub detect_palettes: bool,
+    /// Use the NPC groups inferred from pret's graphics table for graphics ids without a mapping,
+    /// instead of only writing them to `mappings.skeleton.ron` for review
+    pub infer_npc_groups: bool,
+    /// Ask for missing mappings when running in a terminal, saving them to `user_mappings`
+    pub prompt: bool,
+    /// Name of the entry in `profiles` to build with
+    pub profile: Option<String>,
+    pub profiles: HashMap<String, Profile>,
+    pub registry: RegistryConfig,
+    /// Where new games start, instead of the player's bedroom
+    pub spawn: Option<Spawn>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub dir: PathBuf,
+    /// Write maps with [`write_split`](crate::output::write_split)
+    pub split: bool,
+    /// Write Rust constants with [`codegen`](crate::codegen)
+    pub constants: bool,
+    /// Write map names as a [table](crate::output::NameTable) instead of into each map
+    pub localize: bool,
+    /// Language of the converted names
+    pub language: String,
+    /// RON files of translated map names keyed by pret map name, keyed by their language
+    pub translations: BTreeMap<String, PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source: None,
+            reference: "master".to_owned(),
+            game: Game::default(),
+            strict: false,
+            fuzzy: true,
+            filter: Vec::new(),
+            mappings: PathBuf::from("mappings.ron"),
+            user_mappings: Some(PathBuf::from("mappings.user.ron")),
+            edits: PathBuf::from("edits.ron"),
+            output: OutputConfig::default(),
+            cache: PathBuf::from("output"),
+            detect_palettes: false,
+            infer_npc_groups: false,
+            prompt: true,
+            profile: None,
+            profiles: HashMap::new(),
+            registry: RegistryConfig::default(),
+            spawn: None,
+        }
+    }
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("output"),
+            split: false,
+            constants: false,
+            localize: false,
+            language: "en".to_owned(),
+            translations: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads a config file, or the default config if it does not exist.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match path.exists() {
+            true => Ok(toml::from_str(&std::fs::read_to_string(path)?)?),
+            false => Ok(Self::default()),
+        }
+    }
+
+    /// Overrides options with `WORLD_GEN_*` environment variables.
+    pub fn apply_env(&mut self) -> anyhow::Result<()> {
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|value| !value.is_empty())
+        }
+
+        if let Some(source) = var("WORLD_GEN_SOURCE") {
+            self.source = Some(source.into());
+        }
+        if let Some(reference) = var("WORLD_GEN_REF") {
+            self.reference = reference;
+        }
+        if let Some(game) = var("WORLD_GEN_GAME") {
+            self.game = game.parse()?;
+        }
+        if let Some(strict) = var("WORLD_GEN_STRICT") {
+            self.strict = matches!(strict.to_ascii_lowercase().as_str(), "1" | "true" | "yes");
+        }
+        if let Some(mappings) = var("WORLD_GEN_MAPPINGS") {
+            self.mappings = mappings.into();
+        }
+        if let Some(mappings) = var("WORLD_GEN_USER_MAPPINGS") {
+            self.user_mappings = Some(mappings.into());
+        }
+        if let Some(edits) = var("WORLD_GEN_EDITS") {
+            self.edits = edits.into();
+        }
+        if let Some(output) = var("WORLD_GEN_OUTPUT") {
+            self.output.dir = output.into();
+        }
+        if let Some(cache) = var("WORLD_GEN_CACHE_DIR") {
+            self.cache = cache.into();
+        }
+        if let Some(profile) = var("WORLD_GEN_PROFILE") {
+            self.profile = Some(profile);
+        }
+        if let Some(spawn) = var("WORLD_GEN_SPAWN") {
+            self.spawn = Some(spawn.parse()?);
+        }
+        Ok(())
+    }
+
+    /// Overrides options with command line flags, returning the arguments that were not flags.
+    pub fn apply_args(&mut self, args: &[String]) -> anyhow::Result<Vec<String>> {
+        let mut rest = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--source" => self.source = Some(value()?.into()),
+                "--ref" => self.reference = value()?,
+                "--game" => self.game = value()?.parse()?,
+                "--strict" => self.strict = true,
+                "--no-fuzzy" => self.fuzzy = false,
+                "--filter" => self.filter.push(value()?),
+                "--mappings" => self.mappings = value()?.into(),
+                "--user-mappings" => self.user_mappings = Some(value()?.into()),
+                "--edits" => self.edits = value()?.into(),
+                "--output" => self.output.dir = value()?.into(),
+                "--cache" => self.cache = value()?.into(),
+                "--split" => self.output.split = true,
+                "--constants" => self.output.constants = true,
+                "--localize" => self.output.localize = true,
+                "--profile" => self.profile = Some(value()?),
+                "--detect-palettes" => self.detect_palettes = true,
+                "--infer-npc-groups" => self.infer_npc_groups = true,
+                "--no-prompt" => self.prompt = false,
+                "--spawn" => self.spawn = Some(value()?.parse()?),
+                // read before the config is loaded
+                "--config" => {
+                    value()?;
+                }
+                _ => rest.push(arg.clone()),
+            }
+        }
+        Ok(rest)
+    }
+
+    /// Mapping files in the order [`NameMappings::load`](crate::NameMappings::load) layers them.
+    pub fn mapping_layers(&self) -> Vec<&Path> {
+        std::iter::once(self.mappings.as_path())
+            .chain(self.user_mappings.as_deref())
+            .collect()
+    }
+
+    pub fn source(&self) -> anyhow::Result<Box<dyn Source>> {
+        Ok(match &self.source {
+            Some(path) => source::open(path)?,
+            None => {
+                let source = HttpSource::new(&self.reference);
+                match source.pinned() {
+                    Ok(source) => Box::new(source),
+                    Err(err) => {
+                        eprintln!(
+                            "Could not get the commit of {} with error {}, downloading from it directly",
+                            self.reference, err
+                        );
+                        Box::new(HttpSource::new(&self.reference))
+                    }
+                }
+            }
+        })
+    }
+
+    /// Creates a converter with the game, strictness, name matching, filter, profile, spawn
+    /// and localization of this config.
+    pub fn converter(&self) -> anyhow::Result<ConverterBuilder> {
+        let filter = self.filter.clone();
+        let mut builder = ConverterBuilder::default()
+            .game(self.game)
+            .strict(self.strict)
+            .fuzzy(self.fuzzy)
+            .localize(self.output.localize);
+        if let Some(name) = &self.profile {
+            let profile = self
+                .profiles
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown profile {}", name))?;
+            builder = builder.profile(profile.clone());
+        }
+        if let Some(spawn) = &self.spawn {
+            builder = builder.spawn(spawn.clone());
+        }
+        Ok(match filter.is_empty() {
+            true => builder,
+            false => builder.filter(move |map| {
+                filter
+                    .iter()
+                    .any(|prefix| map.data.id.starts_with(prefix.as_str()))
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn flags_override_options_and_leave_other_arguments() {
+        let mut config = Config::default();
+        let rest = config
+            .apply_args(&args(&[
+                "mappings",
+                "--game",
+                "LeafGreen",
+                "--strict",
+                "--filter",
+                "MAP_ROUTE",
+                "--config",
+                "other.toml",
+                "check",
+                "--no-fuzzy",
+            ]))
+            .unwrap();
+        assert_eq!(rest, ["mappings", "check"]);
+        assert_eq!(config.game, Game::LeafGreen);
+        assert!(config.strict);
+        assert!(!config.fuzzy);
+        assert_eq!(config.filter, ["MAP_ROUTE"]);
+
+        assert!(Config::default().apply_args(&args(&["--ref"])).is_err());
+        assert!(Config::default()
+            .apply_args(&args(&["--game", "Emerald"]))
+            .is_err());
+    }
+
+    #[test]
+    fn environment_overrides_options_unless_empty() {
+        // the only test reading WORLD_GEN_* variables, as tests share the environment
+        std::env::set_var("WORLD_GEN_REF", "e7f6dd2");
+        std::env::set_var("WORLD_GEN_STRICT", "Yes");
+        std::env::set_var("WORLD_GEN_OUTPUT", "build");
+        std::env::set_var("WORLD_GEN_CACHE_DIR", "");
+
+        let mut config = Config::default();
+        let applied = config.apply_env();
+
+        for name in [
+            "WORLD_GEN_REF",
+            "WORLD_GEN_STRICT",
+            "WORLD_GEN_OUTPUT",
+            "WORLD_GEN_CACHE_DIR",
+        ] {
+            std::env::remove_var(name);
+        }
+
+        applied.unwrap();
+        assert_eq!(config.reference, "e7f6dd2");
+        assert!(config.strict);
+        assert_eq!(config.output.dir, Path::new("build"));
+        assert_eq!(config.cache, Config::default().cache);
+    }
+}
@@ -0,0 +1,2419 @@
+//! Conversion of parsed pret data into worldlib types.
+//!
+//! [`compile`](crate::compile) drives these over every map, but each step
+//! can be used on its own by editors or validation tools.
+
+use std::{collections::BTreeMap, num::ParseIntError, str::FromStr};
+
+use dashmap::DashMap;
+use firecore_world_builder::{
+    bin::BinaryMap,
+    world::{
+        character::{
+            npc::{
+                group::TrainerGroupId,
+                trainer::{NpcTrainer, TrainerDisable},
+                Npc, NpcId, NpcInteract, NpcMovement, Npcs,
+            },
+            trainer::Trainer,
+            Character,
+        },
+        map::{
+            chunk::{ChunkConnections, Connection, WorldChunk},
+            movement::Elevation,
+            object::{ItemObject, Items, MapObject, Objects, SignObject, Signs},
+            warp::{WarpDestination, WarpEntry},
+            Brightness, PaletteId, TileId, WorldMap, WorldMapSettings, WorldTile,
+        },
+        pokedex::{
+            item::{ItemId, ItemStack},
+            moves::owned::SavedMove,
+            pokemon::{owned::SavedPokemon, stat::StatSet},
+        },
+        positions::{
+            BoundingBox, Coordinate, CoordinateInt, Destination, Direction, Location, Position,
+        },
+        script::{ScriptId, WorldInstruction, WorldScriptData},
+    },
+};
+use hashbrown::{HashMap, HashSet};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use script_parser::{
+    inc::{Command, Script},
+    trainer::party::TrainerPokemon,
+};
+use tinystr::TinyStr16;
+
+use crate::{
+    behavior,
+    builder::WorldMapBuilder,
+    converter::Diagnostics,
+    map::{
+        object::{JsonBgEvent, JsonCoordEvent, JsonObjectEvent},
+        warp::{JsonWarpEvent, MAP_DYNAMIC},
+        wild::Encounters,
+        JsonConnection, JsonMap,
+    },
+    CoordTrigger, Decoration, DynamicWarp, FacingRequirement, GiftPokemon, HealLocation, ItemFlag,
+    MapGroupIndex, MapMetadata, MapType, Messages, NameMappings, NpcMetadata, NpcTrade, ParsedData,
+    ScriptedBattle, ScriptedBattleKind, Scripts, Source, StaticEncounter, TrainerMetadata,
+    VerticalConnections, WarpMetadata, WarpTrigger, Weather, WildTables,
+};
+
+/// Converts a pret map into a [`WorldMap`], downloading its block and border data.
+///
+/// The map's wild tables are taken out of `encounters`.
+pub fn into_world_map(
+    source: &dyn Source,
+    mappings: &NameMappings,
+    data: &ParsedData,
+    encounters: &Encounters,
+    diagnostics: &Diagnostics,
+    map: &JsonMap,
+) -> Option<(WorldMap, MapMetadata)> {
+    let blockdata = |path: &str| {
+        source
+            .blockdata(path)
+            .map_err(|err| {
+                diagnostics.warn(
+                    &map.data.id,
+                    format!("Could not get {} with error {}", path, err),
+                )
+            })
+            .ok()
+    };
+
+    let map_data = blockdata(&map.layout.blockdata_filepath)?;
+    let border_data = blockdata(&map.layout.border_filepath)?;
+
+    let mut mapdata = BinaryMap::load(
+        &map_data,
+        &border_data,
+        map.layout.width * map.layout.height,
+    )?;
+
+    let palettes = into_palettes(
+        mappings,
+        diagnostics,
+        &map.data.id,
+        &map.layout.primary_tileset,
+        &map.layout.secondary_tileset,
+    );
+
+    let id = location(mappings, data, &map.data.id);
+
+    let primary_size = mappings
+        .palettes
+        .sizes
+        .get(&palettes[0])
+        .copied()
+        .unwrap_or_else(|| {
+            diagnostics.warn(
+                &map.data.id,
+                format!(
+                    "Palette {} has no size, splitting tiles after pret's {} primary metatiles",
+                    palettes[0],
+                    behavior::NUM_METATILES_IN_PRIMARY
+                ),
+            );
+            behavior::NUM_METATILES_IN_PRIMARY
+        });
+
+    let behaviors = data.behaviors.map(&map.layout, &mapdata.tiles);
+
+    behavior::refine_movements(
+        &mut mapdata.movements,
+        &behaviors,
+        &data.behaviors.keyed(&mappings.behaviors.passability),
+    );
+
+    let border = mapdata
+        .border
+        .tiles
+        .into_iter()
+        .map(|tile| into_world_tile(primary_size, tile))
+        .collect::<Vec<_>>();
+
+    let mut tables = encounters
+        .remove(&map.data.id)
+        .map(|(.., v)| v)
+        .unwrap_or_default();
+
+    tables.sort_by_key(|(order, ..)| *order);
+
+    let mut tables = tables.into_iter().map(|(.., table)| table);
+
+    let wild = tables.next().unwrap_or_default();
+
+    let alternates = tables.map(|table| table.entries).collect::<Vec<_>>();
+
+    let (npcs, npc_metadata) = into_world_npcs(
+        mappings,
+        data,
+        diagnostics,
+        &map.data.id,
+        &map.data.object_events,
+    );
+
+    let metadata = MapMetadata {
+        postgame: mappings.progression.is_postgame(&map.data.id),
+        group: into_group_index(data, map),
+        scripted_battles: into_scripted_battles(data, map),
+        static_encounters: into_static_encounters(
+            mappings,
+            data,
+            diagnostics,
+            &map.data.id,
+            &map.data.object_events,
+        ),
+        scripts: into_map_scripts(data, map),
+        decorations: into_decorations(mappings, &map.data.object_events),
+        encounter_music: mappings
+            .map
+            .encounter_music
+            .get(&map.data.id)
+            .map(|music| into_music(mappings, diagnostics, &map.data.id, music)),
+        item_flags: into_item_flags(mappings, data, &map.data.bg_events, &map.data.object_events),
+        reflections: behavior::coordinates(
+            &behaviors,
+            map.layout.width,
+            &data.behaviors.values(&mappings.behaviors.reflective),
+        ),
+        terrain: data
+            .behaviors
+            .tag(&behaviors, map.layout.width, &mappings.behaviors.terrain),
+        ledges: data
+            .behaviors
+            .tag(&behaviors, map.layout.width, &mappings.behaviors.ledges),
+        encounter_tiles: behavior::coordinates(
+            &behaviors,
+            map.layout.width,
+            &data.behaviors.values(&mappings.behaviors.encounters),
+        ),
+        water: data
+            .behaviors
+            .tag(&behaviors, map.layout.width, &mappings.behaviors.water),
+        forced: data
+            .behaviors
+            .tag(&behaviors, map.layout.width, &mappings.behaviors.forced),
+        elevations: into_elevations(&map_data),
+        counters: behavior::coordinates(
+            &behaviors,
+            map.layout.width,
+            &data.behaviors.values(&mappings.behaviors.counters),
+        ),
+        facing: into_facing_requirements(&map.data.bg_events),
+        triggers: into_coord_triggers(&map.data.coord_events),
+        weather: into_weather(mappings, diagnostics, &map.data.id, &map.data.weather),
+        battle_background: into_battle_background(mappings, &map.data.battle_scene),
+        map_type: into_map_type(diagnostics, &map.data.id, &map.data.map_type),
+        allow_cycling: map.data.allow_cycling,
+        allow_running: map.data.allow_running,
+        allow_escaping: map.data.allow_escaping,
+        show_map_name: map.data.show_map_name,
+        requires_flash: map.data.requires_flash,
+        region_map_section: into_region_map_section(mappings, &map.data.region_map_section),
+        dynamic_warps: into_dynamic_warps(mappings, data, &map.data.warp_events),
+        warps: into_warp_metadata(
+            mappings,
+            data,
+            &behaviors,
+            map.layout.width,
+            &map.data.warp_events,
+        ),
+        vertical: map
+            .data
+            .connections
+            .as_deref()
+            .map(|connections| into_vertical_connections(mappings, data, connections))
+            .unwrap_or_default(),
+        npcs: npc_metadata,
+        wild_slots: wild.slots,
+        wild_tables: (!alternates.is_empty()).then(|| WildTables {
+            var: mappings
+                .wild
+                .variables
+                .get(&map.data.id)
+                .cloned()
+                .or_else(|| {
+                    diagnostics.warn(
+                        &map.data.id,
+                        format!(
+                            "Map has {} wild tables but no mapped variable",
+                            alternates.len() + 1
+                        ),
+                    );
+                    None
+                }),
+            tables: alternates,
+        }),
+    };
+
+    let world = WorldMapBuilder::new(
+        id,
+        mappings
+            .map
+            .name
+            .get(&map.data.name)
+            .unwrap_or(&map.data.name)
+            // .unwrap_or_else(|| panic!("Cannot get map name mapping for {}", map.data.name))
+            .clone(),
+        map.layout.width as _,
+        map.layout.height as _,
+    )
+    .music(into_music(
+        mappings,
+        diagnostics,
+        &map.data.id,
+        &map.data.music,
+    ))
+    .tiles(
+        mapdata
+            .tiles
+            .into_iter()
+            .map(|tile| into_world_tile(primary_size, tile))
+            .collect(),
+        mapdata.movements,
+    )
+    .palettes(palettes)
+    .border([border[0], border[1], border[2], border[3]])
+    .chunk(
+        map.data
+            .connections
+            .as_ref()
+            .and_then(|connections| into_chunk(mappings, data, diagnostics, map, connections)),
+    )
+    .warps(
+        map.data
+            .warp_events
+            .iter()
+            .flat_map(|warp| into_world_warp(mappings, data, diagnostics, &map.data.id, warp))
+            .collect(),
+    )
+    .wild(wild.entries)
+    .npcs(npcs)
+    .objects(into_world_objects(mappings, &map.data.object_events))
+    .items({
+        let mut items = into_world_items(
+            mappings,
+            data,
+            diagnostics,
+            &map.data.id,
+            &map.data.bg_events,
+        );
+        items.extend(into_world_item_balls(
+            mappings,
+            data,
+            diagnostics,
+            &map.data.id,
+            &map.data.object_events,
+        ));
+        items
+    })
+    .signs(into_world_signs(
+        data,
+        diagnostics,
+        &map.data.id,
+        &map.data.bg_events,
+    ))
+    .settings(WorldMapSettings {
+        fly_position: None,
+        brightness: match map.data.weather == "WEATHER_SHADE" {
+            true => Brightness::Night,
+            false => Brightness::Day,
+        },
+        transition: mappings
+            .map
+            .transition
+            .get(&map.data.battle_scene)
+            .copied()
+            .unwrap_or_else(|| WorldMapSettings::default_transition()),
+    })
+    .build()
+    .map_err(|err| diagnostics.warn(&map.data.id, err.to_string()))
+    .ok()?;
+
+    Some((world, metadata))
+}
+
+/// Converts a pret metatile id into a tile of a map whose primary palette has `primary_size` tiles.
+///
+/// Secondary tiles count from `primary_size`, which [atlases](crate::assets::world_metatiles) follow.
+pub fn into_world_tile(primary_size: TileId, tile: TileId) -> WorldTile {
+    match primary_size > tile {
+        false => WorldTile::Secondary(tile - primary_size),
+        true => WorldTile::Primary(tile),
+    }
+}
+
+/// Converts pret's heal locations, leaving out the ones on maps that were not converted.
+pub fn into_heal_locations(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    maps: &HashMap<Location, WorldMap>,
+) -> Vec<HealLocation> {
+    data.heal_locations
+        .iter()
+        .flat_map(|heal| {
+            let fly = location(mappings, data, &heal.map);
+            if !maps.contains_key(&fly) {
+                diagnostics.warn(
+                    &heal.map,
+                    format!(
+                        "Skipping heal location {} on a map that was not converted",
+                        heal.id
+                    ),
+                );
+                return None;
+            }
+            Some(HealLocation {
+                id: heal.id.clone(),
+                fly: WarpDestination {
+                    location: fly,
+                    position: Destination {
+                        coords: Coordinate {
+                            x: heal.x as _,
+                            y: heal.y as _,
+                        },
+                        direction: None,
+                    },
+                },
+                respawn: heal
+                    .respawn_map
+                    .as_ref()
+                    .map(|map| location(mappings, data, map))
+                    .filter(|location| maps.contains_key(location)),
+            })
+        })
+        .collect()
+}
+
+/// Lowers every script that only uses supported commands into [`WorldScriptData`].
+pub fn create_world_script_data(
+    mappings: &NameMappings,
+    scripts: &Scripts,
+    messages: &Messages,
+) -> WorldScriptData {
+    WorldScriptData {
+        scripts: scripts
+            .par_iter()
+            .flat_map(|r| {
+                let instructions = lower_script(mappings, r.key(), r.value())?;
+                (!instructions.is_empty()).then(|| (r.key().clone(), instructions))
+            })
+            .collect(),
+        messages: messages
+            .par_iter()
+            .map(|r| (r.key().clone(), r.value().clone()))
+            .collect(),
+    }
+}
+
+/// Finds the scripts that [`create_world_script_data`] cannot lower,
+/// so they can be ported by hand.
+pub fn create_raw_scripts(mappings: &NameMappings, scripts: &Scripts) -> BTreeMap<String, Script> {
+    scripts
+        .par_iter()
+        .filter(|r| lower_script(mappings, r.key(), r.value()).is_none())
+        .map(|r| (r.key().clone(), r.value().clone()))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Lowers a script into worldlib instructions, or `None` if it uses an unsupported command.
+///
+/// Commands that cannot be converted, such as sounds without a mapping, also leave the script unlowered.
+fn lower_script(
+    mappings: &NameMappings,
+    id: &ScriptId,
+    script: &Script,
+) -> Option<Vec<WorldInstruction>> {
+    script
+        .commands
+        .iter()
+        .map(|c| into_instruction(mappings, id, c).ok())
+        .collect::<Option<Vec<_>>>()
+        .map(|instructions| instructions.into_iter().flatten().collect())
+}
+
+/// Names of the scripts in a map's `scripts.inc`.
+pub fn into_map_scripts(data: &ParsedData, map: &JsonMap) -> Vec<String> {
+    let mut scripts = data
+        .scripts
+        .iter()
+        .filter(|script| data.is_map_script(script.key(), &map.data.name))
+        .map(|script| script.key().clone())
+        .collect::<Vec<_>>();
+    scripts.sort();
+    scripts
+}
+
+fn into_instruction(
+    mappings: &crate::NameMappings,
+    id: &ScriptId,
+    command: &Command,
+) -> Result<Vec<WorldInstruction>, InstructionError> {
+    Ok(vec![match command.command.as_str() {
+        "end" => WorldInstruction::End,
+        "return" => WorldInstruction::Return,
+        // set variables
+        "setvar" => {
+            WorldInstruction::SetVar(argument(id, command, 0)?.clone(), number(id, command, 1)?)
+        }
+        "setflag" => WorldInstruction::SetFlag(argument(id, command, 0)?.clone()),
+        "specialvar" => WorldInstruction::SpecialVar(
+            argument(id, command, 0)?.clone(),
+            argument(id, command, 1)?.clone(),
+        ),
+        // compare
+        "compare" => WorldInstruction::Compare(
+            argument(id, command, 0)?.clone(),
+            match argument(id, command, 1)?.as_str() {
+                "TRUE" => 1,
+                "FALSE" => 0,
+                _ => number(id, command, 1)?,
+            },
+        ),
+        "call" => WorldInstruction::Call(argument(id, command, 0)?.clone()),
+        // goto/call
+        "goto_if_eq" => WorldInstruction::GotoIfEq(argument(id, command, 0)?.clone()),
+        "goto_if_set" => WorldInstruction::GotoIfSet(
+            argument(id, command, 0)?.clone(),
+            argument(id, command, 1)?.clone(),
+        ),
+        // Player Freezing
+        "lock" => WorldInstruction::Lock,
+        "release" => WorldInstruction::Release,
+        // NPC commands
+        "faceplayer" => WorldInstruction::FacePlayer,
+        "walk_down" => WorldInstruction::Walk(Direction::Down),
+        "walk_up" => WorldInstruction::Walk(Direction::Up),
+        "walk_left" => WorldInstruction::Walk(Direction::Left),
+        "walk_right" => WorldInstruction::Walk(Direction::Right),
+        // Singular trainer battle
+        "trainerbattle_single" => WorldInstruction::TrainerBattleSingle,
+        // Message
+        "msgbox" => WorldInstruction::Msgbox(
+            argument(id, command, 0)?.clone(),
+            command.arguments.get(1).cloned(),
+        ),
+        "textcolor" => WorldInstruction::TextColor(number(id, command, 0)?),
+        "message" => WorldInstruction::Message(argument(id, command, 0)?.clone()),
+        "waitmessage" => WorldInstruction::WaitMessage,
+        // Sound
+        "playfanfare" => {
+            let sound = argument(id, command, 0)?;
+            let sound = sound.get(4..).unwrap_or(sound);
+            let (id, var) =
+                mappings.audio.sounds.get(sound).ok_or_else(|| {
+                    InstructionError::MissingMapping(id.clone(), sound.to_owned())
+                })?;
+            WorldInstruction::PlayFanfare(*id, *var)
+        }
+        "waitfanfare" => WorldInstruction::WaitMessage,
+        // Item
+        // giveitem is a macro that adds the item and shows pret's obtained item message
+        "additem" | "giveitem" => {
+            let item = argument(id, command, 0)?;
+            let item = into_item_id(mappings, item)
+                .ok_or_else(|| InstructionError::MissingMapping(id.clone(), item.clone()))?;
+            // worldlib adds one item at a time
+            let count = match command.arguments.get(1) {
+                Some(..) => number(id, command, 1)?,
+                None => 1,
+            };
+            return Ok(vec![WorldInstruction::AddItem(item); count]);
+        }
+        "checkitemspace" => WorldInstruction::CheckItemSpace(
+            argument(id, command, 0)?.clone(),
+            number(id, command, 1)?,
+        ),
+        "getitemname" => WorldInstruction::GetItemName(
+            number(id, command, 0)?,
+            argument(id, command, 1)?.clone(),
+        ),
+        com => return Err(InstructionError::Unknown(id.to_string(), com.to_owned())),
+    }])
+}
+
+/// Gets an argument of a command, which pret's macros may leave out.
+fn argument<'a>(
+    id: &ScriptId,
+    command: &'a Command,
+    index: usize,
+) -> Result<&'a String, InstructionError> {
+    command
+        .arguments
+        .get(index)
+        .ok_or_else(|| InstructionError::Argument(id.clone(), command.command.clone(), index))
+}
+
+/// Parses a numeric argument of a command.
+fn number<T: FromStr<Err = ParseIntError>>(
+    id: &ScriptId,
+    command: &Command,
+    index: usize,
+) -> Result<T, InstructionError> {
+    let argument = argument(id, command, index)?;
+    argument
+        .parse()
+        .map_err(|err| InstructionError::ParseInt(id.clone(), argument.clone(), err))
+}
+
+#[derive(Debug)]
+enum InstructionError {
+    Unknown(ScriptId, String),
+    Argument(ScriptId, String, usize),
+    ParseInt(ScriptId, String, ParseIntError),
+    MissingMapping(ScriptId, String),
+}
+
+impl std::error::Error for InstructionError {}
+
+impl std::fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstructionError::Unknown(id, command) => {
+                write!(f, "Script {} uses unsupported command {}", id, command)
+            }
+            InstructionError::Argument(id, command, index) => write!(
+                f,
+                "Script {} is missing argument {} of command {}",
+                id, index, command
+            ),
+            InstructionError::ParseInt(id, text, err) => write!(
+                f,
+                "Could not parse number {} in script {} with error {}",
+                text, id, err
+            ),
+            InstructionError::MissingMapping(id, name) => {
+                write!(f, "Script {} uses {}, which has no mapping", id, name)
+            }
+        }
+    }
+}
+
+/// Finds the group of a map and its index in the group.
+pub fn into_group_index(data: &ParsedData, map: &JsonMap) -> Option<MapGroupIndex> {
+    data.groups.iter().enumerate().find_map(|(group, g)| {
+        g.maps
+            .iter()
+            .position(|name| name == &map.data.name)
+            .map(|index| MapGroupIndex {
+                name: g.name.clone(),
+                group,
+                index,
+            })
+    })
+}
+
+/// Gets the location of a pret map id, generating one if it has no mapping.
+///
+/// Generated locations are named after the map's group, such as
+/// `indoor_pallet` for maps in `gMapGroup_IndoorPallet`.
+pub fn location(mappings: &NameMappings, data: &ParsedData, id: &str) -> Location {
+    mappings
+        .map
+        .id
+        .get(id)
+        .cloned()
+        .unwrap_or_else(|| loc(data, id))
+}
+
+/// Generates the location of every map without a mapping, keyed by pret map id,
+/// failing if two maps would end up with the same location, whether mapped or generated.
+pub fn generated_locations(
+    mappings: &NameMappings,
+    data: &ParsedData,
+) -> anyhow::Result<BTreeMap<String, Location>> {
+    let (mapped, generated) = data
+        .maps
+        .iter()
+        .map(|map| (map.key().clone(), location(mappings, data, map.key())))
+        .partition::<BTreeMap<_, _>, _>(|(id, ..)| mappings.map.id.contains_key(id));
+
+    let mut used = HashMap::new();
+
+    let mut collisions = Vec::new();
+
+    for (id, location) in mapped.iter().chain(generated.iter()) {
+        if let Some(other) = used.insert(*location, id) {
+            collisions.push(format!("{} and {} are both {:?}", other, id, location));
+        }
+    }
+
+    match collisions.is_empty() {
+        true => Ok(generated),
+        false => Err(anyhow::anyhow!(
+            "Map locations collide: {}",
+            collisions.join(", ")
+        )),
+    }
+}
+
+fn loc(data: &ParsedData, id: &str) -> Location {
+    let group = data.maps.get(id).and_then(|map| {
+        data.groups
+            .iter()
+            .find(|group| group.maps.contains(&map.data.name))
+            .map(|group| group_id(&group.name))
+    });
+    Location {
+        map: Some(group.unwrap_or_else(|| "unnamed".parse().unwrap())),
+        index: truncate_id(id),
+    }
+}
+
+/// Converts a pret map group name, such as `indoor_pallet` for `gMapGroup_IndoorPallet`.
+fn group_id(group: &str) -> TinyStr16 {
+    shorten(&snake_case(
+        group.strip_prefix("gMapGroup_").unwrap_or(group),
+    ))
+}
+
+/// Removes the `MAP_` prefix from a map id.
+fn truncate_id(id: &str) -> TinyStr16 {
+    shorten(id.strip_prefix("MAP_").unwrap_or(id))
+}
+
+/// Replaces the end of names that are too long with a hash of the whole name
+/// so different names stay different.
+fn shorten(name: &str) -> TinyStr16 {
+    if name.len() > 16 {
+        format!(
+            "{}_{:04x}",
+            &name[..11],
+            crc32fast::hash(name.as_bytes()) & 0xffff
+        )
+        .parse()
+    } else {
+        name.parse()
+    }
+    .unwrap()
+}
+
+/// Converts a CamelCase pret name into snake_case, such as `pallet_town` for `PalletTown`.
+pub fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if let Some(previous) = previous {
+            if (c.is_ascii_uppercase() && !previous.is_ascii_uppercase())
+                || (c.is_ascii_digit() && !previous.is_ascii_digit())
+            {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+        previous = Some(c);
+    }
+    snake
+}
+
+/// Bits of a pret block above its metatile and collision that hold its elevation.
+pub const BLOCK_ELEVATION_SHIFT: u16 = 12;
+
+/// Reads the elevation of each block of a map's blockdata, in row order.
+///
+/// Elevation keeps bridges apart from the water below them, and `15` lets
+/// a tile be crossed from any elevation, as on bridges and in gates.
+pub fn into_elevations(blockdata: &[u8]) -> Vec<Elevation> {
+    blockdata
+        .chunks_exact(2)
+        .map(|block| {
+            Elevation((u16::from_le_bytes([block[0], block[1]]) >> BLOCK_ELEVATION_SHIFT) as _)
+        })
+        .collect()
+}
+
+/// Converts a map's connections, returning `None` if it has none.
+///
+/// Dive and emerge connections are left to [`into_vertical_connections`].
+///
+/// Offsets keep pret's sign, where a positive offset moves the neighbor right of
+/// or below this map's top left corner along the shared edge. Connections whose
+/// neighbor would not touch the edge are warned about but kept.
+pub fn into_chunk(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &JsonMap,
+    json_connections: &[JsonConnection],
+) -> Option<WorldChunk> {
+    match json_connections.is_empty() {
+        true => None,
+        false => {
+            let mut connections = ChunkConnections::new();
+            for connection in json_connections {
+                let direction = match connection.direction.as_str() {
+                    "left" => Direction::Left,
+                    "right" => Direction::Right,
+                    "up" => Direction::Up,
+                    "down" => Direction::Down,
+                    "dive" | "emerge" => continue,
+                    direction => {
+                        diagnostics.warn(
+                            &map.data.id,
+                            format!(
+                                "Skipping connection to {} with unknown direction {}",
+                                connection.map, direction
+                            ),
+                        );
+                        continue;
+                    }
+                };
+                if let Some(neighbor) = data.maps.get(&connection.map) {
+                    let (length, neighbor_length) = match direction {
+                        Direction::Up | Direction::Down => {
+                            (map.layout.width, neighbor.layout.width)
+                        }
+                        Direction::Left | Direction::Right => {
+                            (map.layout.height, neighbor.layout.height)
+                        }
+                    };
+                    if connection.offset >= length as isize
+                        || connection.offset + neighbor_length as isize <= 0
+                    {
+                        diagnostics.warn(
+                            &map.data.id,
+                            format!(
+                                "Connection to {} with offset {} does not line up with the {:?} edge",
+                                connection.map, connection.offset, direction
+                            ),
+                        );
+                    }
+                }
+                let offset = match connection.offset.try_into() {
+                    Ok(offset) => offset,
+                    Err(..) => {
+                        diagnostics.warn(
+                            &map.data.id,
+                            format!(
+                                "Skipping connection to {} with out of range offset {}",
+                                connection.map, connection.offset
+                            ),
+                        );
+                        continue;
+                    }
+                };
+                if !connections.contains_key(&direction) {
+                    connections.insert(direction, Vec::new());
+                }
+                connections.get_mut(&direction).unwrap().push(Connection(
+                    location(mappings, data, &connection.map),
+                    offset,
+                ))
+            }
+            (!connections.is_empty()).then(|| WorldChunk { connections })
+        }
+    }
+}
+
+/// Finds the maps a map's dive and emerge connections lead to, which worldlib's
+/// [`WorldChunk`] has no directions for.
+pub fn into_vertical_connections(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    connections: &[JsonConnection],
+) -> VerticalConnections {
+    let mut vertical = VerticalConnections::default();
+    for connection in connections {
+        let target = match connection.direction.as_str() {
+            "dive" => &mut vertical.dive,
+            "emerge" => &mut vertical.emerge,
+            _ => continue,
+        };
+        *target = Some(location(mappings, data, &connection.map));
+    }
+    vertical
+}
+
+/// Describes how each warp of a map is taken, from the [warp](crate::BehaviorMappings::warps)
+/// behavior of its tile. Warps on tiles without one are taken by stepping on them.
+pub fn into_warp_metadata(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    behaviors: &[behavior::Behavior],
+    width: usize,
+    warps: &[JsonWarpEvent],
+) -> Vec<WarpMetadata> {
+    let triggers = data.behaviors.keyed(&mappings.behaviors.warps);
+    warps
+        .iter()
+        .enumerate()
+        .map(|(index, warp)| {
+            let destination = match warp.destination == MAP_DYNAMIC {
+                true => "dynamic".to_owned(),
+                false => location(mappings, data, &warp.destination)
+                    .index
+                    .to_string(),
+            };
+            let trigger = (warp.x >= 0 && warp.y >= 0)
+                .then(|| warp.y as usize * width + warp.x as usize)
+                .and_then(|index| behaviors.get(index))
+                .and_then(|behavior| triggers.get(behavior))
+                .copied()
+                .unwrap_or(WarpTrigger::Tile);
+            WarpMetadata {
+                id: format!("{}_{}_{}", destination, warp.x, warp.y),
+                index: index as _,
+                coords: Coordinate {
+                    x: warp.x as _,
+                    y: warp.y as _,
+                },
+                warp_on_tile: !matches!(trigger, WarpTrigger::Arrow(..)),
+                move_on_exit: matches!(trigger, WarpTrigger::Door | WarpTrigger::Stairs(..)),
+                facing: match trigger {
+                    WarpTrigger::Arrow(direction) | WarpTrigger::Stairs(direction) => {
+                        Some(direction)
+                    }
+                    _ => None,
+                },
+                arrival: trigger.arrival(),
+                // set by mark_music_changes once every map is converted
+                change_music: true,
+                one_way: trigger == WarpTrigger::Fall,
+            }
+        })
+        .collect()
+}
+
+/// Faces players arriving through warps without a direction the way the
+/// [arrival](WarpMetadata::arrival) of the warp they land on says.
+pub fn face_arrivals(
+    maps: &DashMap<Location, WorldMap>,
+    metadata: &DashMap<Location, MapMetadata>,
+) {
+    for mut map in maps.iter_mut() {
+        for warp in map.warps.iter_mut() {
+            let position = &mut warp.destination.position;
+            if position.direction.is_some() {
+                continue;
+            }
+            position.direction = metadata
+                .get(&warp.destination.location)
+                .and_then(|metadata| {
+                    metadata
+                        .warps
+                        .iter()
+                        .find(|arrival| arrival.coords == position.coords)
+                        .and_then(|arrival| arrival.arrival)
+                });
+        }
+    }
+}
+
+/// Sets whether each warp [changes the music](WarpMetadata::change_music), by comparing
+/// the music of the maps on both sides. Warps to unknown maps always change it.
+pub fn mark_music_changes(
+    maps: &DashMap<Location, WorldMap>,
+    metadata: &DashMap<Location, MapMetadata>,
+) {
+    for mut entry in metadata.iter_mut() {
+        let (location, metadata) = entry.pair_mut();
+        let map = match maps.get(location) {
+            Some(map) => map,
+            None => continue,
+        };
+        for warp in metadata.warps.iter_mut() {
+            warp.change_music = map
+                .warps
+                .iter()
+                .find(|entry| entry.area.min == warp.coords)
+                .and_then(|entry| maps.get(&entry.destination.location))
+                .map(|destination| destination.music != map.music)
+                .unwrap_or(true);
+        }
+    }
+}
+
+/// Finds the warps of a map whose destinations are [decided at runtime](JsonWarpEvent::is_dynamic).
+pub fn into_dynamic_warps(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    warps: &[JsonWarpEvent],
+) -> Vec<DynamicWarp> {
+    warps
+        .iter()
+        .filter(|warp| warp.is_dynamic())
+        .map(|warp| DynamicWarp {
+            coords: Coordinate {
+                x: warp.x as _,
+                y: warp.y as _,
+            },
+            map: (warp.destination != MAP_DYNAMIC)
+                .then(|| location(mappings, data, &warp.destination)),
+        })
+        .collect()
+}
+
+/// Converts a warp event, returning `None` if its destination map is unknown.
+///
+/// Warps to a warp id the destination map does not have land on the map's
+/// [fallback](crate::MapMappings::warp_fallbacks) position, or on its first warp.
+pub fn into_world_warp(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    warp: &JsonWarpEvent,
+) -> Option<WarpEntry> {
+    // dynamic warps are kept by into_dynamic_warps
+    if warp.is_dynamic() {
+        return None;
+    }
+
+    let destination = location(mappings, data, &warp.destination);
+
+    let target = match data.maps.get(&warp.destination) {
+        Some(target) => target,
+        None => {
+            diagnostics.warn(
+                map,
+                format!(
+                    "Skipping warp at {}, {} to unknown map {}",
+                    warp.x, warp.y, warp.destination
+                ),
+            );
+            return None;
+        }
+    };
+
+    let coords = match target.data.warp_events.get(warp.dest_warp_id as usize) {
+        Some(w) => Coordinate {
+            x: w.x as _,
+            y: w.y as _,
+        },
+        None => {
+            let fallback = match mappings.map.warp_fallbacks.get(&warp.destination) {
+                Some((x, y)) => Some(Coordinate {
+                    x: *x as _,
+                    y: *y as _,
+                }),
+                None => target.data.warp_events.first().map(|w| Coordinate {
+                    x: w.x as _,
+                    y: w.y as _,
+                }),
+            };
+            diagnostics.warn(
+                map,
+                format!(
+                    "Warp at {}, {} goes to missing warp {} of {}, {}",
+                    warp.x,
+                    warp.y,
+                    warp.dest_warp_id,
+                    warp.destination,
+                    match fallback {
+                        Some(coords) => format!("landing at {}, {} instead", coords.x, coords.y),
+                        None => "skipping it".to_owned(),
+                    }
+                ),
+            );
+            fallback?
+        }
+    };
+
+    Some(WarpEntry {
+        area: BoundingBox {
+            min: Coordinate {
+                x: warp.x as _,
+                y: warp.y as _,
+            },
+            max: Coordinate {
+                x: warp.x as _,
+                y: warp.y as _,
+            },
+        },
+        destination: WarpDestination {
+            location: destination,
+            position: Destination {
+                coords,
+                direction: None,
+            },
+        },
+    })
+}
+
+/// Most scripts followed through `goto` and `call` when looking through a script.
+const SCRIPT_DEPTH: usize = 4;
+
+/// pret commands that start a battle with the NPC whose script they are in.
+const TRAINER_BATTLES: [&str; 3] = [
+    "trainerbattle_single",
+    "trainerbattle_double",
+    "trainerbattle_no_intro",
+];
+
+/// Finds the command that starts a trainer battle in a script or the scripts it jumps to.
+fn trainer_battle(data: &ParsedData, script: &str, depth: usize) -> Option<Command> {
+    if depth > SCRIPT_DEPTH {
+        return None;
+    }
+    let script = data.scripts.get(script)?;
+    script
+        .commands
+        .iter()
+        .find_map(|command| match command.command.as_str() {
+            "goto" | "call" => trainer_battle(data, command.arguments.first()?, depth + 1),
+            battle
+                if TRAINER_BATTLES
+                    .iter()
+                    .any(|b| battle.eq_ignore_ascii_case(b)) =>
+            {
+                Some(command.clone())
+            }
+            _ => None,
+        })
+}
+
+/// Gets the commands of a script and of the scripts it mentions, such as in `goto_if_eq`,
+/// in the order they appear along with the script they are in.
+fn script_commands(data: &ParsedData, script: &str) -> Vec<(String, Command)> {
+    let mut commands = Vec::new();
+    walk_script(data, script, 0, &mut HashSet::new(), &mut commands);
+    commands
+}
+
+fn walk_script(
+    data: &ParsedData,
+    script: &str,
+    depth: usize,
+    visited: &mut HashSet<String>,
+    commands: &mut Vec<(String, Command)>,
+) {
+    if depth > SCRIPT_DEPTH || !visited.insert(script.to_owned()) {
+        return;
+    }
+    if let Some(script) = data.scripts.get(script) {
+        for command in script.commands.iter() {
+            commands.push((script.key().clone(), command.clone()));
+            for argument in command.arguments.iter() {
+                if data.scripts.contains_key(argument) {
+                    walk_script(data, argument, depth + 1, visited, commands);
+                }
+            }
+        }
+    }
+}
+
+/// Converts the pokemon a script gives with `givemon` or `givecustommon`,
+/// including species stored in a variable with `setvar` first.
+///
+/// Pokemon whose species or level cannot be found are left out.
+pub fn into_gift_pokemon(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    script: &str,
+) -> Vec<GiftPokemon> {
+    let mut vars = HashMap::<String, String>::new();
+    let mut gifts = Vec::new();
+    for (script, command) in script_commands(data, script) {
+        match command.command.as_str() {
+            "setvar" => {
+                if let [var, value, ..] = command.arguments.as_slice() {
+                    vars.insert(var.clone(), value.clone());
+                }
+            }
+            "givemon" | "givecustommon" => {
+                let species = match command.arguments.first() {
+                    Some(species) => vars.get(species).unwrap_or(species),
+                    None => continue,
+                };
+                let species = species_name(mappings, species);
+                let level = match command
+                    .arguments
+                    .get(1)
+                    .and_then(|level| level.parse().ok())
+                {
+                    Some(level) => level,
+                    None => {
+                        diagnostics.warn(
+                            map,
+                            format!("Cannot parse level of gift pokemon in {}", script),
+                        );
+                        continue;
+                    }
+                };
+                let pokemon = match data.pokedex.try_get_named(&species) {
+                    Some(pokemon) => pokemon,
+                    None => {
+                        diagnostics.warn(map, format!("Cannot get pokemon id {}", species));
+                        continue;
+                    }
+                };
+                gifts.push(GiftPokemon {
+                    pokemon: SavedPokemon {
+                        pokemon: pokemon.id,
+                        level,
+                        gender: None,
+                        item: command
+                            .arguments
+                            .get(2)
+                            .filter(|item| item.as_str() != "ITEM_NONE")
+                            .and_then(|item| {
+                                into_trainer_item(mappings, data, diagnostics, map, item)
+                            }),
+                        ..Default::default()
+                    },
+                    script,
+                });
+            }
+            _ => (),
+        }
+    }
+    gifts
+}
+
+/// Converts the in-game trade a script offers, found through the trade id it mentions.
+pub fn into_npc_trade(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    script: &str,
+) -> Option<NpcTrade> {
+    let (id, trade) = script_commands(data, script)
+        .into_iter()
+        .flat_map(|(.., command)| command.arguments)
+        .find_map(|argument| data.trades.get_key_value(&argument))?;
+    let pokemon = |species: &str| {
+        let name = species_name(mappings, species);
+        data.pokedex
+            .try_get_named(&name)
+            .map(|pokemon| pokemon.id)
+            .or_else(|| {
+                diagnostics.warn(
+                    map,
+                    format!("Cannot get pokemon id {} for trade {}", name, id),
+                );
+                None
+            })
+    };
+    Some(NpcTrade {
+        id: id.clone(),
+        requested: pokemon(&trade.requested_species)?,
+        pokemon: SavedPokemon {
+            pokemon: pokemon(&trade.species)?,
+            gender: None,
+            item: Some(&trade.held_item)
+                .filter(|item| item.as_str() != "ITEM_NONE")
+                .and_then(|item| into_trainer_item(mappings, data, diagnostics, map, item)),
+            ..Default::default()
+        },
+        nickname: trade.nickname.clone(),
+        trainer: trade.ot_name.clone(),
+    })
+}
+
+/// Finds the `setwildbattle` command of a script, following `goto` and `call`.
+fn wild_battle(data: &ParsedData, script: &str, depth: usize) -> Option<Command> {
+    if depth > SCRIPT_DEPTH {
+        return None;
+    }
+    let script = data.scripts.get(script)?;
+    script
+        .commands
+        .iter()
+        .find_map(|command| match command.command.as_str() {
+            "goto" | "call" => wild_battle(data, command.arguments.first()?, depth + 1),
+            "setwildbattle" => Some(command.clone()),
+            _ => None,
+        })
+}
+
+/// Converts the object events whose scripts battle a wild pokemon,
+/// such as Snorlax or the Voltorb disguised as items.
+pub fn into_static_encounters(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    events: &[JsonObjectEvent],
+) -> Vec<StaticEncounter> {
+    events
+        .iter()
+        .flat_map(|event| {
+            let battle = wild_battle(data, &event.script, 0)?;
+            let species = species_name(mappings, battle.arguments.first()?);
+            let level = battle.arguments.get(1)?.parse().ok().or_else(|| {
+                diagnostics.warn(
+                    map,
+                    format!("Cannot parse level of static encounter {}", event.script),
+                );
+                None
+            })?;
+            let pokemon = data.pokedex.try_get_named(&species).or_else(|| {
+                diagnostics.warn(map, format!("Cannot get pokemon id {}", species));
+                None
+            })?;
+            Some(StaticEncounter {
+                coords: Coordinate {
+                    x: event.x as _,
+                    y: event.y as _,
+                },
+                pokemon: SavedPokemon {
+                    pokemon: pokemon.id,
+                    level,
+                    gender: None,
+                    item: battle
+                        .arguments
+                        .get(2)
+                        .filter(|item| item.as_str() != "ITEM_NONE")
+                        .and_then(|item| into_trainer_item(mappings, data, diagnostics, map, item)),
+                    ..Default::default()
+                },
+                flag: (!event.flag.is_empty() && event.flag != "0").then(|| event.flag.clone()),
+                script: event.script.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Converts the trainer an object event battles with, from its `trainerbattle` command
+/// and the sight range in `trainer_sight_or_berry_tree_id`.
+///
+/// Also returns the trainer's name, if it has one.
+fn into_npc_trainer(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    event: &JsonObjectEvent,
+    battle: &Command,
+) -> Option<(Option<String>, NpcTrainer, TrainerMetadata)> {
+    let double_battle = battle.command.eq_ignore_ascii_case("trainerbattle_double");
+    let no_intro = battle
+        .command
+        .eq_ignore_ascii_case("trainerbattle_no_intro");
+    let mut args = battle.arguments.iter();
+    let id = args.next()?;
+    let message = |id: &String| {
+        data.messages.get(id).map(|m| m.clone()).or_else(|| {
+            diagnostics.warn(map, format!("Cannot get trainer message {}", id));
+            None
+        })
+    };
+    let encounter = match no_intro {
+        true => Vec::new(),
+        false => message(args.next()?)?,
+    };
+    let defeat = message(args.next()?)?;
+    let t = data.trainers.get(id).or_else(|| {
+        diagnostics.warn(map, format!("Cannot get trainer {}", id));
+        None
+    })?;
+    let party = data.parties.get(&t.party).or_else(|| {
+        diagnostics.warn(map, format!("Could not get party for {}", id));
+        None
+    })?;
+    let sight = event
+        .trainer_sight_or_berry_tree_id
+        .parse()
+        .unwrap_or_default();
+
+    fn get_group(t: &script_parser::trainer::Trainer) -> TrainerGroupId {
+        fn get(t: &script_parser::trainer::Trainer) -> Option<TrainerGroupId> {
+            let text = t.pic.split_once("TRAINER_PIC_").map(|(.., r)| r)?;
+            let split = text.split_once('_')?;
+            if split.0.eq_ignore_ascii_case("RS") {
+                return None;
+            }
+            Some(text.to_ascii_lowercase().parse().ok()?)
+        }
+
+        get(t).unwrap_or_else(|| "placeholder".parse().unwrap())
+    }
+
+    let ai_flags = t
+        .ai_flags
+        .iter()
+        .filter(|flag| !flag.is_empty() && flag.as_str() != "0")
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let metadata = TrainerMetadata {
+        id: id.clone(),
+        double_battle: double_battle || t.double_battle,
+        partners: Vec::new(),
+        items: t
+            .items
+            .iter()
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty() && !item.eq_ignore_ascii_case("ITEM_NONE"))
+            .flat_map(|item| into_trainer_item(mappings, data, diagnostics, map, item))
+            .collect(),
+        difficulty: ai_flags
+            .iter()
+            .flat_map(|flag| mappings.trainers.ai.get(flag))
+            .max()
+            .copied(),
+        ai_flags,
+        sight_directions: into_sight_directions(mappings, event),
+        buried: event.trainer_type == "TRAINER_TYPE_BURIED",
+    };
+
+    let trainer = NpcTrainer {
+        group: get_group(t),
+        character: Trainer {
+            party: into_trainer_party(mappings, data, diagnostics, map, party),
+            bag: Default::default(), //trainer.items.in,
+            worth: 0,
+        },
+        sight: match sight == 0 {
+            true => None,
+            false => Some(sight),
+        },
+        encounter,
+        defeat,
+        badge: None,
+        disable: TrainerDisable::DisableSelf,
+    };
+
+    Some((t.name.clone(), trainer, metadata))
+}
+
+/// Gets how far a wandering NPC can walk from where it starts on each axis,
+/// only letting it walk along the axes of its movement type's directions.
+pub fn into_wander_range(event: &JsonObjectEvent, directions: &HashSet<Direction>) -> Coordinate {
+    let horizontal =
+        directions.contains(&Direction::Left) || directions.contains(&Direction::Right);
+    let vertical = directions.contains(&Direction::Up) || directions.contains(&Direction::Down);
+    Coordinate {
+        x: match horizontal {
+            true => event.movement_range_x as _,
+            false => 0,
+        },
+        y: match vertical {
+            true => event.movement_range_y as _,
+            false => 0,
+        },
+    }
+}
+
+/// Gets the area a wandering NPC stays within, or `None` if pret leaves it unbounded
+/// with a range of 0 on every axis it walks along.
+pub fn into_wander_area(
+    event: &JsonObjectEvent,
+    directions: &HashSet<Direction>,
+) -> Option<BoundingBox> {
+    let range = into_wander_range(event, directions);
+    (range.x != 0 || range.y != 0).then(|| BoundingBox {
+        min: Coordinate {
+            x: event.x as CoordinateInt - range.x,
+            y: event.y as CoordinateInt - range.y,
+        },
+        max: Coordinate {
+            x: event.x as CoordinateInt + range.x,
+            y: event.y as CoordinateInt + range.y,
+        },
+    })
+}
+
+/// Gets the directions a trainer looks for the player in.
+///
+/// Normal trainers only see in the directions their movement type faces,
+/// while `TRAINER_TYPE_SEE_ALL_DIRECTIONS` and buried trainers see in every direction.
+pub fn into_sight_directions(mappings: &NameMappings, event: &JsonObjectEvent) -> Vec<Direction> {
+    const DIRECTIONS: [Direction; 4] = [
+        Direction::Down,
+        Direction::Up,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    match event.trainer_type.as_str() {
+        "TRAINER_TYPE_NONE" => Vec::new(),
+        "TRAINER_TYPE_SEE_ALL_DIRECTIONS" | "TRAINER_TYPE_BURIED" => DIRECTIONS.to_vec(),
+        _ => match mappings.npcs.movement.get(&event.movement_type) {
+            Some((.., directions)) if !directions.is_empty() => DIRECTIONS
+                .into_iter()
+                .filter(|direction| directions.contains(direction))
+                .collect(),
+            _ => vec![Direction::Down],
+        },
+    }
+}
+
+/// Gets the dex name of a pret species constant, such as `MR-MIME` for `SPECIES_MR_MIME`,
+/// using the [species mapping](NameMappings::species) if there is one.
+pub fn species_name(mappings: &NameMappings, species: &str) -> String {
+    match mappings.species.get(species) {
+        Some(name) => name.clone(),
+        None => species
+            .strip_prefix("SPECIES_")
+            .unwrap_or(species)
+            .replace('_', "-"),
+    }
+}
+
+/// Gets the dex name of a pret move constant, such as `THUNDER SHOCK` for `MOVE_THUNDER_SHOCK`,
+/// using the [move mapping](NameMappings::moves) if there is one.
+pub fn move_name(mappings: &NameMappings, m: &str) -> String {
+    match mappings.moves.get(m) {
+        Some(name) => name.clone(),
+        None => m.strip_prefix("MOVE_").unwrap_or(m).replace('_', " "),
+    }
+}
+
+/// Gets the item held by a trainer's pokemon or kept in their bag,
+/// using the [item mapping](NameMappings::items) if there is one.
+pub fn into_trainer_item(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    item: &str,
+) -> Option<ItemId> {
+    if let Some(id) = mappings.items.get(item) {
+        return Some(*id);
+    }
+    let name = item.strip_prefix("ITEM_").unwrap_or(item).replace('_', " ");
+    data.itemdex
+        .try_get_named(&name)
+        .map(|item| item.id)
+        .or_else(|| {
+            diagnostics.warn(map, format!("Cannot get trainer item id {}", name));
+            None
+        })
+}
+
+/// Converts a pret trainer party, with the levels, held items and moves it sets.
+///
+/// Pokemon whose species cannot be found are left out.
+pub fn into_trainer_party(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    party: &[TrainerPokemon],
+) -> Vec<SavedPokemon> {
+    party
+        .iter()
+        .flat_map(|p| {
+            let id = species_name(mappings, &p.species);
+            let pokemon = data.pokedex.try_get_named(&id).or_else(|| {
+                diagnostics.warn(map, format!("Cannot get pokemon id {}", id));
+                None
+            })?;
+            let mut saved = SavedPokemon {
+                pokemon: pokemon.id,
+                level: p.level,
+                gender: None,
+                // pret scales ivs from 0 to 255
+                ivs: StatSet::uniform((p.ivs as u16 * 31 / 255) as u8),
+                ..Default::default()
+            };
+            if let Some(item) = &p.item {
+                saved.item = into_trainer_item(mappings, data, diagnostics, map, item);
+            }
+            for m in p.moves.iter().flatten() {
+                if m.eq_ignore_ascii_case("MOVE_NONE") {
+                    continue;
+                }
+                let id = move_name(mappings, m);
+                match data.movedex.try_get_named(&id) {
+                    Some(m) => saved.moves.push(SavedMove::from(m.id)),
+                    None => diagnostics.warn(map, format!("Cannot get move id {}", id)),
+                }
+            }
+            Some(saved)
+        })
+        .collect()
+}
+
+/// Scripts that may hold pret's [`RESET_MAP_FLAGS`] script, tried in order.
+pub const NEW_GAME_SCRIPTS: [&str; 2] = ["data/scripts/new_game.inc", "data/event_scripts.s"];
+
+/// Label of the script that sets the flags hiding objects when a new game starts.
+pub const RESET_MAP_FLAGS: &str = "EventScript_ResetAllMapFlags";
+
+/// Prefix of the flags that hide objects while they are set.
+pub const HIDE_FLAG_PREFIX: &str = "FLAG_HIDE_";
+
+/// Gets the hide flags set by the [`RESET_MAP_FLAGS`] script of a file, if the file has it.
+pub fn new_game_flags(file: &str) -> Option<Vec<String>> {
+    let mut lines = file.lines().map(str::trim);
+    lines.find(|line| {
+        line.strip_prefix(RESET_MAP_FLAGS)
+            .map(|end| end.starts_with(':'))
+            .unwrap_or_default()
+    })?;
+    Some(
+        lines
+            .take_while(|line| !matches!(*line, "end" | "return"))
+            .flat_map(|line| line.strip_prefix("setflag"))
+            .map(str::trim)
+            .filter(|flag| flag.starts_with(HIDE_FLAG_PREFIX))
+            .map(ToOwned::to_owned)
+            .collect(),
+    )
+}
+
+/// Finds the hide flags set when a new game starts that no script ever clears,
+/// which permanently hide the objects they control.
+///
+/// Objects hidden by flags that some script clears, such as story NPCs, are kept.
+/// Returns `None` if pret's new game script could not be found.
+pub fn permanently_set_flags(source: &dyn Source, scripts: &Scripts) -> Option<HashSet<String>> {
+    let file = NEW_GAME_SCRIPTS
+        .iter()
+        .flat_map(|path| source.text(path).ok())
+        .find(|file| file.contains(RESET_MAP_FLAGS))?;
+    let mut set = new_game_flags(&file)?.into_iter().collect::<HashSet<_>>();
+    // global scripts that are not loaded may clear flags too
+    let cleared = file
+        .lines()
+        .flat_map(|line| line.trim().strip_prefix("clearflag"))
+        .map(|flag| flag.trim().to_owned())
+        .chain(scripts.iter().flat_map(|script| {
+            script
+                .commands
+                .iter()
+                .filter(|command| command.command == "clearflag")
+                .flat_map(|command| command.arguments.first().cloned())
+                .collect::<Vec<_>>()
+        }))
+        .collect::<HashSet<_>>();
+    set.retain(|flag| !cleared.contains(flag));
+    Some(set)
+}
+
+/// Commands that only start or end a conversation, such as `faceplayer`.
+const CONVERSATION_COMMANDS: [&str; 6] = [
+    "lock",
+    "lockall",
+    "faceplayer",
+    "release",
+    "releaseall",
+    "end",
+];
+
+/// Gets the message of a script that does nothing but show one message box.
+fn simple_message(data: &ParsedData, script: &Script) -> Option<Vec<Vec<String>>> {
+    let mut commands = script
+        .commands
+        .iter()
+        .filter(|command| !CONVERSATION_COMMANDS.contains(&command.command.as_str()));
+    let command = commands.next()?;
+    if commands.next().is_some() {
+        return None;
+    }
+    match command.command.as_str() {
+        "msgbox" | "message" => data
+            .messages
+            .get(command.arguments.first()?)
+            .map(|message| message.clone()),
+        _ => None,
+    }
+}
+
+/// Longest id an NPC can have, which is a [`TinyStr16`]
+const NPC_ID_LENGTH: usize = 16;
+
+/// Gets the readable part of an object event's script label,
+/// such as `Youngster` in `Route1_EventScript_Youngster`.
+fn script_label_name(label: &str) -> Option<&str> {
+    let name = match label.split_once("_EventScript_") {
+        Some((.., name)) => name,
+        None => label.split_once('_')?.1,
+    };
+    (!name.is_empty()).then(|| name)
+}
+
+/// Splits a camel case label into words, such as `OldMan_2` into `Old`, `Man` and `2`.
+fn label_words(label: &str) -> Vec<String> {
+    let mut words = Vec::<String>::new();
+    let mut previous = None::<char>;
+    for char in label.chars() {
+        if char == '_' {
+            previous = None;
+            continue;
+        }
+        let split = match previous {
+            Some(previous) => {
+                char.is_ascii_uppercase()
+                    && (previous.is_ascii_lowercase() || previous.is_ascii_digit())
+            }
+            None => true,
+        };
+        match words.last_mut() {
+            Some(word) if !split => word.push(char),
+            _ => words.push(char.to_string()),
+        }
+        previous = Some(char);
+    }
+    words
+}
+
+/// Derives an id for each object event from its script label, such as `youngster`,
+/// falling back to `npc_N` for events without one.
+///
+/// Events that share a label are told apart by their index.
+fn into_npc_ids(events: &[JsonObjectEvent]) -> Vec<NpcId> {
+    let mut used = HashSet::new();
+    events
+        .iter()
+        .enumerate()
+        .map(|(index, event)| {
+            script_label_name(&event.script)
+                .map(|name| label_words(name).join("_").to_ascii_lowercase())
+                .filter(|id| !id.is_empty() && id.is_ascii())
+                .map(|mut id| {
+                    id.truncate(NPC_ID_LENGTH);
+                    if used.contains(&id) {
+                        let suffix = format!("_{}", index);
+                        id.truncate(NPC_ID_LENGTH - suffix.len());
+                        id.push_str(&suffix);
+                    }
+                    id
+                })
+                .and_then(|id| {
+                    let npc = id.parse().ok()?;
+                    used.insert(id);
+                    Some(npc)
+                })
+                .unwrap_or_else(|| format!("npc_{}", index).parse().unwrap())
+        })
+        .collect()
+}
+
+/// Converts the object events that have an NPC group mapping.
+pub fn into_world_npcs(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    events: &[JsonObjectEvent],
+) -> (Npcs, HashMap<NpcId, NpcMetadata>) {
+    let ids = into_npc_ids(events);
+
+    let (npcs, metadata) = events
+        .par_iter()
+        .zip(ids)
+        .flat_map(|(event, id)| {
+            // static encounters are converted by into_static_encounters
+            if wild_battle(data, &event.script, 0).is_some() {
+                return None;
+            }
+            if let Some(group) = mappings.npcs.groups.get(&event.graphics_id) {
+                let (movement, directions) = mappings
+                    .npcs
+                    .movement
+                    .get(&event.movement_type)
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        diagnostics.warn(
+                            map,
+                            format!(
+                                "Unsupported movement type {}, making NPC stand still",
+                                event.movement_type
+                            ),
+                        );
+                        Default::default()
+                    });
+
+                let mut interact = NpcInteract::Nothing;
+
+                let mut trainer = None;
+                let mut name = String::new();
+                let mut metadata = NpcMetadata {
+                    animation: mappings.npcs.animations.get(&event.movement_type).cloned(),
+                    flag: (!event.flag.is_empty() && event.flag != "0").then(|| event.flag.clone()),
+                    script: (!event.script.is_empty()).then(|| event.script.clone()),
+                    ..Default::default()
+                };
+
+                if let Some(script) = data.scripts.get(&event.script) {
+                    let script = script.value();
+
+                    metadata.postgame = script.commands.iter().any(|command| {
+                        command
+                            .arguments
+                            .iter()
+                            .any(|arg| mappings.progression.postgame_flags.contains(arg))
+                    });
+                    if let Some(message) = simple_message(data, script) {
+                        interact = NpcInteract::Message(message);
+                    }
+
+                    if !(event.trainer_type.eq_ignore_ascii_case("TRAINER_TYPE_NONE")) {
+                        if let Some((trainer_name, npc_trainer, trainer_metadata)) =
+                            trainer_battle(data, &script.name, 0).and_then(|battle| {
+                                into_npc_trainer(mappings, data, diagnostics, map, event, &battle)
+                            })
+                        {
+                            if let Some(trainer_name) = trainer_name {
+                                name = trainer_name;
+                            }
+                            trainer = Some(npc_trainer);
+                            metadata.trainer = Some(trainer_metadata);
+                        }
+                    }
+                }
+
+                if matches!(interact, NpcInteract::Nothing) && !event.script.is_empty() {
+                    interact = NpcInteract::Script(event.script.clone());
+                }
+
+                metadata.gifts = into_gift_pokemon(mappings, data, diagnostics, map, &event.script);
+                metadata.trade = into_npc_trade(mappings, data, diagnostics, map, &event.script);
+
+                if movement {
+                    metadata.wander_area = into_wander_area(event, &directions);
+                }
+
+                if name.is_empty() {
+                    name = match script_label_name(&event.script) {
+                        Some(label) => label_words(label).join(" "),
+                        None => format!("NPC {}-{}", event.x, event.y),
+                    };
+                }
+
+                let group = group.parse().unwrap();
+                Some((
+                    id,
+                    Npc {
+                        id,
+                        character: Character::new(
+                            name,
+                            Position {
+                                coords: Coordinate {
+                                    x: event.x as _,
+                                    y: event.y as _,
+                                },
+                                direction: *directions.iter().next().unwrap_or(&Direction::Down),
+                                elevation: Elevation(event.elevation),
+                            },
+                        ),
+                        group,
+                        movement: match movement {
+                            true => {
+                                let empty = directions.len() <= 1;
+                                let mut vec = Vec::with_capacity(1 + if empty { 0 } else { 1 });
+                                vec.push(NpcMovement::Move(into_wander_range(event, &directions)));
+                                if !empty {
+                                    vec.push(NpcMovement::Look(directions));
+                                }
+                                vec
+                            }
+                            false => match directions.len() <= 1 {
+                                true => Vec::new(),
+                                false => vec![NpcMovement::Look(directions)],
+                            },
+                        },
+                        origin: None,
+                        interact,
+                        trainer,
+                    },
+                    metadata,
+                ))
+            } else {
+                None
+            }
+        })
+        .map(|(id, npc, metadata)| ((id, npc), (id, metadata)))
+        .unzip::<_, _, Npcs, Vec<_>>();
+
+    let mut metadata = metadata
+        .into_iter()
+        .filter(|(.., metadata)| !metadata.is_empty())
+        .collect::<HashMap<_, _>>();
+
+    // NPCs that share a double battle, such as twins, fight the player together
+    let doubles = metadata
+        .iter()
+        .flat_map(|(id, metadata)| metadata.trainer.as_ref().map(|trainer| (id, trainer)))
+        .filter(|(.., trainer)| trainer.double_battle)
+        .map(|(id, trainer)| (*id, trainer.id.clone()))
+        .collect::<Vec<_>>();
+
+    for (id, trainer) in doubles.iter() {
+        if let Some(metadata) = metadata.get_mut(id).and_then(|m| m.trainer.as_mut()) {
+            metadata.partners = doubles
+                .iter()
+                .filter(|(other, t)| other != id && t == trainer)
+                .map(|(other, ..)| *other)
+                .collect();
+        }
+    }
+
+    (npcs, metadata)
+}
+
+/// Finds battles started by the scripts in a map's `scripts.inc`.
+pub fn into_scripted_battles(data: &ParsedData, map: &JsonMap) -> Vec<ScriptedBattle> {
+    let mut battles = data
+        .scripts
+        .par_iter()
+        .filter(|script| data.is_map_script(script.key(), &map.data.name))
+        .flat_map_iter(|script| {
+            script
+                .commands
+                .iter()
+                .flat_map(|command| {
+                    let kind = match command.command.as_str() {
+                        "special"
+                            if command.arguments.first().map(String::as_str)
+                                == Some("StartOldManTutorialBattle") =>
+                        {
+                            ScriptedBattleKind::CatchTutorial
+                        }
+                        "trainerbattle_earlyrival" => ScriptedBattleKind::EarlyRival {
+                            trainer: command.arguments.first()?.clone(),
+                        },
+                        _ => return None,
+                    };
+                    Some(ScriptedBattle {
+                        script: script.key().clone(),
+                        kind,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    battles.sort_by(|a, b| a.script.cmp(&b.script));
+    battles
+}
+
+/// Converts the object events that have an object mapping.
+pub fn into_world_objects(mappings: &NameMappings, events: &[JsonObjectEvent]) -> Objects {
+    events
+        .par_iter()
+        .flat_map(
+            |event| match mappings.objects.objects.get(&event.graphics_id) {
+                Some(id) => Some({
+                    (
+                        Coordinate {
+                            x: event.x as _,
+                            y: event.y as _,
+                        },
+                        MapObject { group: *id },
+                    )
+                }),
+                None => None,
+            },
+        )
+        .collect()
+}
+
+/// Converts the object events that have a decoration mapping.
+pub fn into_decorations(mappings: &NameMappings, events: &[JsonObjectEvent]) -> Vec<Decoration> {
+    events
+        .iter()
+        .flat_map(|event| {
+            let decoration = mappings.objects.decorations.get(&event.graphics_id)?;
+            Some(Decoration {
+                sprite: decoration.sprite.clone(),
+                coords: Coordinate {
+                    x: event.x as _,
+                    y: event.y as _,
+                },
+                width: decoration.width,
+                height: decoration.height,
+                flag: (!event.flag.is_empty() && event.flag != "0").then(|| event.flag.clone()),
+            })
+        })
+        .collect()
+}
+
+/// Gets an item id from a pret constant, such as `potion` from `ITEM_POTION`,
+/// using the [item mapping](NameMappings::items) if there is one.
+pub fn into_item_id(mappings: &NameMappings, item: &str) -> Option<ItemId> {
+    match mappings.items.get(item) {
+        Some(id) => Some(*id),
+        None => item
+            .strip_prefix("ITEM_")?
+            .to_ascii_lowercase()
+            .parse()
+            .ok(),
+    }
+}
+
+/// Converts hidden item background events.
+pub fn into_world_items(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    events: &[JsonBgEvent],
+) -> Items {
+    events
+        .par_iter()
+        .filter(|event| event.type_ == "hidden_item")
+        .flat_map(|event| {
+            Some((
+                Coordinate {
+                    x: event.x as _,
+                    y: event.y as _,
+                },
+                ItemObject {
+                    item: ItemStack {
+                        item: {
+                            let id = into_item_id(mappings, event.item.as_ref()?)?;
+                            firecore_world_builder::world::pokedex::Dex::try_get(&data.itemdex, &id)
+                                .or_else(|| {
+                                    if !id.eq_ignore_ascii_case("NONE") {
+                                        diagnostics.warn(
+                                            map,
+                                            format!(
+                                                "Cannot get item id {} for hidden item",
+                                                id.as_str()
+                                            ),
+                                        );
+                                    }
+                                    None
+                                })?
+                                .id
+                        },
+                        count: event.quantity?,
+                    },
+                    hidden: event.underfoot?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Gets the flags set once hidden items and item balls are picked up.
+pub fn into_item_flags(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    events: &[JsonBgEvent],
+    objects: &[JsonObjectEvent],
+) -> Vec<ItemFlag> {
+    events
+        .iter()
+        .filter(|event| event.type_ == "hidden_item")
+        .flat_map(|event| Some((event.x, event.y, event.flag.as_ref()?)))
+        .chain(
+            objects
+                .iter()
+                .filter(|event| matches!(into_item_ball(mappings, data, event), Some(Ok(..))))
+                .map(|event| (event.x, event.y, &event.flag)),
+        )
+        .filter(|(.., flag)| !flag.is_empty() && flag.as_str() != "0")
+        .map(|(x, y, flag)| ItemFlag {
+            coords: Coordinate {
+                x: x as _,
+                y: y as _,
+            },
+            flag: flag.clone(),
+        })
+        .collect()
+}
+
+/// Graphics of the object events picked up as items.
+pub const ITEM_BALL_GRAPHICS: &str = "OBJ_EVENT_GFX_ITEM_BALL";
+
+/// Gets the item given by an item ball's `finditem` script,
+/// or the id of the item if it is not in the item dex.
+pub fn into_item_ball(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    event: &JsonObjectEvent,
+) -> Option<Result<ItemStack, ItemId>> {
+    if event.graphics_id != ITEM_BALL_GRAPHICS {
+        return None;
+    }
+    let script = data.scripts.get(&event.script)?;
+    let command = script
+        .commands
+        .iter()
+        .find(|command| command.command == "finditem")?;
+    let id = into_item_id(mappings, command.arguments.first()?)?;
+    let item = match firecore_world_builder::world::pokedex::Dex::try_get(&data.itemdex, &id) {
+        Some(item) => item.id,
+        None => return Some(Err(id)),
+    };
+    Some(Ok(ItemStack {
+        item,
+        count: command
+            .arguments
+            .get(1)
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(1),
+    }))
+}
+
+/// Converts the object events that are item balls.
+pub fn into_world_item_balls(
+    mappings: &NameMappings,
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    events: &[JsonObjectEvent],
+) -> Items {
+    events
+        .par_iter()
+        .flat_map(|event| {
+            let item = into_item_ball(mappings, data, event)?
+                .map_err(|id| {
+                    diagnostics.warn(
+                        map,
+                        format!("Cannot get item id {} for item ball", id.as_str()),
+                    )
+                })
+                .ok()?;
+            Some((
+                Coordinate {
+                    x: event.x as _,
+                    y: event.y as _,
+                },
+                ItemObject {
+                    item,
+                    hidden: false,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Converts sign background events whose script shows a message,
+/// reporting signs whose text cannot be found.
+pub fn into_world_signs(
+    data: &ParsedData,
+    diagnostics: &Diagnostics,
+    map: &str,
+    events: &[JsonBgEvent],
+) -> Signs {
+    events
+        .par_iter()
+        .filter(|event| event.type_ == "sign")
+        .flat_map(|event| {
+            let script = event.script.as_ref()?;
+            let message = sign_message(data, script, 0).or_else(|| {
+                diagnostics.warn(
+                    map,
+                    format!(
+                        "Cannot find text of sign at {}, {} with script {}",
+                        event.x, event.y, script
+                    ),
+                );
+                None
+            })?;
+            Some((
+                Coordinate {
+                    x: event.x as _,
+                    y: event.y as _,
+                },
+                SignObject { message },
+            ))
+        })
+        .collect()
+}
+
+/// Finds the first message shown by a script or the scripts it jumps to.
+fn sign_message(data: &ParsedData, script: &str, depth: usize) -> Option<Vec<Vec<String>>> {
+    if depth > SCRIPT_DEPTH {
+        return None;
+    }
+    let script = data.scripts.get(script)?;
+    script.commands.iter().find_map(|command| {
+        let argument = command.arguments.first()?;
+        match command.command.as_str() {
+            "msgbox" | "message" => data.messages.get(argument).map(|m| m.clone()),
+            "goto" | "call" => sign_message(data, argument, depth + 1),
+            _ => None,
+        }
+    })
+}
+
+/// Converts a map's weather, which is `None` for `WEATHER_NONE` and for
+/// weather only used for [brightness](Brightness), such as `WEATHER_SHADE`.
+pub fn into_weather(
+    mappings: &NameMappings,
+    diagnostics: &Diagnostics,
+    map: &str,
+    weather: &str,
+) -> Option<Weather> {
+    let mapped = mappings.map.weather.get(weather).copied();
+    if mapped.is_none() && !matches!(weather, "WEATHER_NONE" | "WEATHER_SHADE") {
+        diagnostics.warn(
+            map,
+            format!("Unsupported weather {}, leaving the map clear", weather),
+        );
+    }
+    mapped
+}
+
+/// Gets the region map section of a pret `MAPSEC_*` constant, using the
+/// [section mapping](crate::MapMappings::sections) if there is one,
+/// such as `route_1` for `MAPSEC_ROUTE_1` otherwise.
+pub fn into_region_map_section(mappings: &NameMappings, section: &str) -> Option<String> {
+    if let Some(section) = mappings.map.sections.get(section) {
+        return Some(section.clone());
+    }
+    section
+        .strip_prefix("MAPSEC_")
+        .filter(|section| !section.is_empty() && *section != "NONE")
+        .map(str::to_ascii_lowercase)
+}
+
+/// Converts a pret map type, such as `MAP_TYPE_UNDERGROUND`.
+pub fn into_map_type(diagnostics: &Diagnostics, map: &str, map_type: &str) -> Option<MapType> {
+    Some(match map_type {
+        "MAP_TYPE_TOWN" => MapType::Town,
+        "MAP_TYPE_CITY" => MapType::City,
+        "MAP_TYPE_ROUTE" => MapType::Route,
+        "MAP_TYPE_UNDERGROUND" => MapType::Underground,
+        "MAP_TYPE_UNDERWATER" => MapType::Underwater,
+        "MAP_TYPE_OCEAN_ROUTE" => MapType::OceanRoute,
+        "MAP_TYPE_INDOOR" => MapType::Indoor,
+        "MAP_TYPE_SECRET_BASE" => MapType::SecretBase,
+        _ => {
+            if map_type != "MAP_TYPE_NONE" {
+                diagnostics.warn(map, format!("Unknown map type {}", map_type));
+            }
+            return None;
+        }
+    })
+}
+
+/// Gets the battle background of a pret battle scene, using the
+/// [background mapping](crate::MapMappings::battle_backgrounds) if there is one,
+/// such as `gym` for `MAP_BATTLE_SCENE_GYM` otherwise.
+pub fn into_battle_background(mappings: &NameMappings, scene: &str) -> Option<String> {
+    if let Some(background) = mappings.map.battle_backgrounds.get(scene) {
+        return Some(background.clone());
+    }
+    scene
+        .strip_prefix("MAP_BATTLE_SCENE_")
+        .filter(|scene| !scene.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+/// Converts a map's trigger coord events, joining the tiles that run the same script
+/// under the same condition into one trigger.
+pub fn into_coord_triggers(events: &[JsonCoordEvent]) -> Vec<CoordTrigger> {
+    let mut triggers = Vec::<CoordTrigger>::new();
+    for event in events.iter().filter(|event| event.type_ == "trigger") {
+        let (script, var, value) = match (&event.script, &event.var, &event.var_value) {
+            (Some(script), Some(var), Some(value)) if !script.is_empty() && script != "0x0" => {
+                (script, var, value)
+            }
+            _ => continue,
+        };
+        let coords = Coordinate {
+            x: event.x as _,
+            y: event.y as _,
+        };
+        match triggers.iter_mut().find(|trigger| {
+            &trigger.script == script
+                && &trigger.var == var
+                && &trigger.value == value
+                && trigger.elevation == event.elevation
+        }) {
+            Some(trigger) => trigger.coords.push(coords),
+            None => triggers.push(CoordTrigger {
+                script: script.clone(),
+                var: var.clone(),
+                value: value.clone(),
+                elevation: event.elevation,
+                coords: vec![coords],
+            }),
+        }
+    }
+    for trigger in triggers.iter_mut() {
+        trigger.coords.sort_by_key(|coords| (coords.y, coords.x));
+    }
+    triggers
+}
+
+/// Finds background events that can only be interacted with from some directions,
+/// from pret's `player_facing_dir`.
+pub fn into_facing_requirements(events: &[JsonBgEvent]) -> Vec<FacingRequirement> {
+    let mut requirements = events
+        .iter()
+        .flat_map(|event| {
+            let direction = match event.player_facing_dir.as_deref()? {
+                "BG_EVENT_PLAYER_FACING_NORTH" => Direction::Up,
+                "BG_EVENT_PLAYER_FACING_SOUTH" => Direction::Down,
+                "BG_EVENT_PLAYER_FACING_EAST" => Direction::Right,
+                "BG_EVENT_PLAYER_FACING_WEST" => Direction::Left,
+                _ => return None,
+            };
+            Some(FacingRequirement {
+                coords: Coordinate {
+                    x: event.x as _,
+                    y: event.y as _,
+                },
+                directions: vec![direction],
+            })
+        })
+        .collect::<Vec<_>>();
+    requirements.sort_by_key(|requirement| (requirement.coords.y, requirement.coords.x));
+    requirements
+}
+
+/// Gets the palettes of a map's primary and secondary tilesets.
+pub fn into_palettes(
+    mappings: &NameMappings,
+    diagnostics: &Diagnostics,
+    map: &str,
+    primary: &str,
+    secondary: &str,
+) -> [PaletteId; 2] {
+    let primary = mappings
+        .palettes
+        .primary
+        .get(primary)
+        .copied()
+        .unwrap_or_else(|| {
+            diagnostics.warn(map, format!("Unknown primary tileset {}", primary));
+            0
+        });
+    let secondary = mappings
+        .palettes
+        .secondary
+        .get(secondary)
+        .copied()
+        .unwrap_or_else(|| {
+            diagnostics.warn(map, format!("Unknown secondary tileset {}", secondary));
+            13
+        });
+
+    [primary, secondary]
+}
+
+/// Gets the music id for a pret music constant.
+///
+/// Unmapped constants use their [derived name](crate::music_name).
+pub fn into_music(
+    mappings: &NameMappings,
+    diagnostics: &Diagnostics,
+    map: &str,
+    music: &str,
+) -> TinyStr16 {
+    mappings.music.get(music).copied().unwrap_or_else(|| {
+        let name = crate::music_name(music);
+        diagnostics.warn(
+            map,
+            match name {
+                Some(name) => format!("Cannot find music {}, using {}", music, name),
+                None => format!("Cannot find music {}", music),
+            },
+        );
+        name.unwrap_or_else(|| "pallet".parse().unwrap())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_ids_fit_and_stay_apart() {
+        assert_eq!(snake_case("TownsAndRoutes"), "towns_and_routes");
+        assert_eq!(snake_case("SSAnne"), "ssanne");
+        assert_eq!(group_id("gMapGroup_IndoorPallet").as_str(), "indoor_pallet");
+        assert_eq!(truncate_id("MAP_ROUTE1").as_str(), "ROUTE1");
+
+        // ids longer than a TinyStr16 keep a hash of their whole name
+        assert_eq!(
+            shorten("FIVE_ISLAND_LOST_CAVE_ROOM1").as_str(),
+            "FIVE_ISLAND_033c"
+        );
+        assert_eq!(
+            shorten("FIVE_ISLAND_LOST_CAVE_ROOM2").as_str(),
+            "FIVE_ISLAND_5286"
+        );
+    }
+
+    #[test]
+    fn npc_ids_and_names_come_from_script_labels() {
+        assert_eq!(
+            script_label_name("Route1_EventScript_Youngster"),
+            Some("Youngster")
+        );
+        assert_eq!(script_label_name("0x0"), None);
+        assert_eq!(label_words("OldMan_2"), ["Old", "Man", "2"]);
+
+        let event = |script: &str| -> JsonObjectEvent {
+            serde_json::from_str(&format!(
+                r#"{{
+                    "graphics_id": "OBJ_EVENT_GFX_YOUNGSTER",
+                    "x": 13,
+                    "y": 22,
+                    "elevation": 3,
+                    "movement_type": "MOVEMENT_TYPE_WANDER_AROUND",
+                    "movement_range_x": 1,
+                    "movement_range_y": 1,
+                    "trainer_type": "TRAINER_TYPE_NONE",
+                    "trainer_sight_or_berry_tree_id": "0",
+                    "script": "{}",
+                    "flag": "0"
+                }}"#,
+                script
+            ))
+            .unwrap()
+        };
+        let ids = into_npc_ids(&[
+            event("ViridianCity_EventScript_Youngster"),
+            event("ViridianCity_EventScript_Youngster"),
+            event("0x0"),
+            event("ViridianCity_EventScript_OldMan"),
+        ]);
+        assert_eq!(
+            ids.iter().map(|id| id.as_str()).collect::<Vec<_>>(),
+            ["youngster", "youngster_1", "npc_2", "old_man"]
+        );
+    }
+
+    fn script(commands: &[&str]) -> Script {
+        Script {
+            name: "PalletTown_EventScript_Gift".to_owned(),
+            location: None,
+            commands: commands
+                .iter()
+                .map(|text| {
+                    let (command, arguments) = text.split_once(' ').unwrap_or((*text, ""));
+                    Command {
+                        command: command.to_owned(),
+                        arguments: arguments
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|argument| !argument.is_empty())
+                            .map(str::to_owned)
+                            .collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn scripts_with_unconvertible_commands_stay_unlowered() {
+        let mappings = NameMappings::default();
+        let id = "PalletTown_EventScript_Gift".to_owned();
+
+        let gift = script(&[
+            "lock",
+            "faceplayer",
+            "giveitem ITEM_POTION, 2",
+            "release",
+            "end",
+        ]);
+        let instructions = lower_script(&mappings, &id, &gift).unwrap();
+        assert_eq!(instructions.len(), 6);
+        assert_eq!(
+            instructions
+                .iter()
+                .filter(|instruction| matches!(instruction, WorldInstruction::AddItem(..)))
+                .count(),
+            2
+        );
+
+        for commands in [
+            vec!["lock", "playfanfare MUS_OBTAIN_ITEM", "end"],
+            vec!["textcolor NPC_TEXT_COLOR_MALE"],
+            vec!["checkitemspace ITEM_POTION"],
+            vec!["goto_if_unset FLAG_GOT_POTION, PalletTown_EventScript_Gift"],
+        ] {
+            assert!(lower_script(&mappings, &id, &script(&commands)).is_none());
+        }
+    }
+}
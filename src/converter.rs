@@ -0,0 +1,576 @@
+//! Configurable entry point to the conversion pipeline.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use dashmap::DashMap;
+use firecore_world_builder::world::{
+    character::npc::group::NpcGroupId,
+    map::{warp::WarpDestination, WorldMap},
+    positions::{Coordinate, Destination, Location},
+};
+use hashbrown::HashSet;
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    convert,
+    map::{wild::Encounters, JsonMap},
+    Edits, MapMetadata, NameMappings, ParsedData, Source, WorldData,
+};
+
+/// Version of the game whose data is converted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Game {
+    #[default]
+    FireRed,
+    LeafGreen,
+}
+
+impl Game {
+    /// Suffix of pret labels that only apply to this game.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Game::FireRed => "FireRed",
+            Game::LeafGreen => "LeafGreen",
+        }
+    }
+}
+
+impl std::str::FromStr for Game {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "firered" => Ok(Game::FireRed),
+            "leafgreen" => Ok(Game::LeafGreen),
+            _ => Err(anyhow::anyhow!("Unknown game {}", s)),
+        }
+    }
+}
+
+/// Where a new game starts, which is the player's bedroom in Pallet Town by default.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spawn {
+    /// pret map id, such as `MAP_PALLET_TOWN_PLAYERS_HOUSE_2F`
+    pub map: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Default for Spawn {
+    fn default() -> Self {
+        Self {
+            map: "MAP_PALLET_TOWN_PLAYERS_HOUSE_2F".to_owned(),
+            x: 6,
+            y: 6,
+        }
+    }
+}
+
+impl std::str::FromStr for Spawn {
+    type Err = anyhow::Error;
+
+    /// Parses a spawn written as `MAP_ID,x,y`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(',').map(str::trim);
+        let mut part = || {
+            parts
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Spawn {} is not written as MAP_ID,x,y", s))
+        };
+        Ok(Self {
+            map: part()?.to_owned(),
+            x: part()?.parse()?,
+            y: part()?.parse()?,
+        })
+    }
+}
+
+/// Content left out of a reduced build, such as a demo.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Prefixes of pret map ids to leave out, such as `MAP_CELADON_CITY_GAME_CORNER`
+    pub exclude_maps: Vec<String>,
+    /// NPC groups to remove from every map
+    pub exclude_npc_groups: Vec<NpcGroupId>,
+    /// Leave out maps that can only be reached after the Elite Four
+    pub exclude_postgame: bool,
+    /// Convert maps that look like [placeholders](Profile::is_placeholder) instead of skipping them
+    pub keep_placeholders: bool,
+    /// Parts of pret map ids that mark a map as a placeholder
+    pub placeholder_maps: Vec<String>,
+    /// Leave out NPCs hidden at the start of a new game by a flag that is
+    /// [never cleared](convert::permanently_set_flags)
+    pub skip_hidden_npcs: bool,
+}
+
+impl Profile {
+    /// Whether a map is too small to be real, has nothing on it and cannot be entered,
+    /// or has an id matching [`placeholder_maps`](Profile::placeholder_maps).
+    pub fn is_placeholder(&self, map: &JsonMap) -> bool {
+        let data = &map.data;
+        map.layout.width * map.layout.height <= 1
+            || (data.object_events.is_empty()
+                && data.warp_events.is_empty()
+                && data.bg_events.is_empty()
+                && data.connections.as_ref().map(Vec::is_empty).unwrap_or(true))
+            || self
+                .placeholder_maps
+                .iter()
+                .any(|pattern| data.id.contains(pattern.as_str()))
+    }
+
+    fn excludes(&self, mappings: &NameMappings, map: &JsonMap) -> bool {
+        (self.exclude_postgame && mappings.progression.is_postgame(&map.data.id))
+            || self
+                .exclude_maps
+                .iter()
+                .any(|prefix| map.data.id.starts_with(prefix.as_str()))
+    }
+}
+
+type MapFilter = Box<dyn Fn(&JsonMap) -> bool + Send + Sync>;
+
+type MapHook = Box<dyn Fn(&mut WorldMap) + Send + Sync>;
+
+/// Converts [`ParsedData`] into [`WorldData`].
+///
+/// ```no_run
+/// # fn run(source: &dyn firecore_world_gen::Source, data: firecore_world_gen::ParsedData) -> anyhow::Result<()> {
+/// use firecore_world_gen::converter::{Converter, Game};
+///
+/// let conversion = Converter::builder()
+///     .game(Game::LeafGreen)
+///     .strict(true)
+///     .filter(|map| map.data.id.starts_with("MAP_ROUTE"))
+///     .hook(|map| map.settings.fly_position = None)
+///     .build()
+///     .convert(source, data)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Converter {
+    mappings: NameMappings,
+    edits: Edits,
+    game: Game,
+    strict: bool,
+    fuzzy: bool,
+    filter: Option<MapFilter>,
+    hooks: Vec<MapHook>,
+    profile: Profile,
+    spawn: Option<Spawn>,
+    localize: bool,
+}
+
+#[derive(Default)]
+pub struct ConverterBuilder {
+    mappings: NameMappings,
+    edits: Edits,
+    game: Game,
+    strict: bool,
+    fuzzy: bool,
+    filter: Option<MapFilter>,
+    hooks: Vec<MapHook>,
+    profile: Profile,
+    spawn: Option<Spawn>,
+    localize: bool,
+}
+
+impl ConverterBuilder {
+    pub fn mappings(mut self, mappings: NameMappings) -> Self {
+        self.mappings = mappings;
+        self
+    }
+
+    pub fn edits(mut self, edits: Edits) -> Self {
+        self.edits = edits;
+        self
+    }
+
+    pub fn game(mut self, game: Game) -> Self {
+        self.game = game;
+        self
+    }
+
+    /// Fail the conversion if any diagnostics are reported.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Fall back to the [closest](NameMappings::fuzzy_map_name) map name mapping
+    /// for map names without an exact one.
+    pub fn fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    /// Only convert maps the filter returns `true` for.
+    pub fn filter(mut self, filter: impl Fn(&JsonMap) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Leave out the content of a profile, recording what was removed.
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Start new games somewhere other than the [default](Spawn::default) spawn.
+    pub fn spawn(mut self, spawn: Spawn) -> Self {
+        self.spawn = Some(spawn);
+        self
+    }
+
+    /// Name each map after its pret map name, such as `PalletTown`, and collect
+    /// the display names into [`WorldData::names`] so they can be translated.
+    pub fn localize(mut self, localize: bool) -> Self {
+        self.localize = localize;
+        self
+    }
+
+    /// Run a function on every map after it is converted, before edits are applied.
+    /// Hooks run in the order they are added.
+    pub fn hook(mut self, hook: impl Fn(&mut WorldMap) + Send + Sync + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    pub fn build(self) -> Converter {
+        Converter {
+            mappings: self.mappings,
+            edits: self.edits,
+            game: self.game,
+            strict: self.strict,
+            fuzzy: self.fuzzy,
+            filter: self.filter,
+            hooks: self.hooks,
+            profile: self.profile,
+            spawn: self.spawn,
+            localize: self.localize,
+        }
+    }
+}
+
+/// Result of [`Converter::convert`].
+pub struct Conversion {
+    pub world: WorldData,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Content left out by the [`Profile`]
+    pub removed: Vec<String>,
+    /// Locations generated for maps without a mapping, keyed by pret map id
+    pub generated: BTreeMap<String, Location>,
+}
+
+/// A problem found while converting, which did not stop the conversion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// pret id of the map being converted, or what was being read if it was not a map
+    pub map: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.map, self.message)
+    }
+}
+
+/// Collects [`Diagnostic`]s from the conversion threads.
+#[derive(Default)]
+pub struct Diagnostics {
+    inner: Mutex<Vec<Diagnostic>>,
+}
+
+impl Diagnostics {
+    pub fn warn(&self, map: &str, message: impl Into<String>) {
+        let diagnostic = Diagnostic {
+            map: map.to_owned(),
+            message: message.into(),
+        };
+        eprintln!("{}", diagnostic);
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.push(diagnostic);
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<Diagnostic> {
+        self.inner.into_inner().unwrap_or_default()
+    }
+}
+
+impl Converter {
+    pub fn builder() -> ConverterBuilder {
+        ConverterBuilder::default()
+    }
+
+    pub fn convert(self, source: &dyn Source, mut data: ParsedData) -> anyhow::Result<Conversion> {
+        let diagnostics = Diagnostics::default();
+
+        // includes palettes matched by tileset::detect_palettes
+        let mut missing = self
+            .mappings
+            .palettes
+            .primary
+            .iter()
+            .filter(|(.., palette)| !self.mappings.palettes.sizes.contains_key(*palette))
+            .collect::<Vec<_>>();
+        missing.sort();
+        for (tileset, palette) in missing {
+            diagnostics.warn(
+                tileset,
+                format!(
+                    "Primary tileset has palette {} with no size mapping",
+                    palette
+                ),
+            );
+        }
+
+        let generated = convert::generated_locations(&self.mappings, &data)?;
+
+        println!("Converting wild encounters...");
+
+        let encounters = Encounters::default();
+
+        let wild = std::mem::take(&mut data.wild.wild_encounter_groups);
+
+        let label = self.game.label();
+
+        wild.into_par_iter().enumerate().for_each(|(group, g)| {
+            let fields = &g.fields;
+            g.encounters
+                .into_par_iter()
+                .enumerate()
+                .filter(|(.., e)| {
+                    e.base_label
+                        .get(e.base_label.len().saturating_sub(label.len())..)
+                        .map(|end| end.eq_ignore_ascii_case(label))
+                        .unwrap_or_default()
+                })
+                .for_each(|(index, e)| {
+                    encounters
+                        .entry(e.map.clone())
+                        .or_default()
+                        .push(((group, index), e.into(fields, &data.pokedex)));
+                });
+        });
+
+        println!("Created {} wild encounters", encounters.len());
+
+        let new_maps = DashMap::<Location, WorldMap>::new();
+        let metadata = DashMap::<Location, MapMetadata>::new();
+        let removed = Mutex::new(Vec::new());
+        let names = DashMap::<String, String>::new();
+
+        let hidden = match self.profile.skip_hidden_npcs {
+            true => convert::permanently_set_flags(source, &data.scripts).unwrap_or_else(|| {
+                diagnostics.warn(
+                    convert::RESET_MAP_FLAGS,
+                    "Could not find the new game script, keeping hidden NPCs",
+                );
+                Default::default()
+            }),
+            false => Default::default(),
+        };
+
+        // pret names of parsed maps, which are not used as fuzzy matches of other maps
+        let parsed = data
+            .maps
+            .iter()
+            .map(|map| map.data.name.clone())
+            .collect::<HashSet<_>>();
+
+        println!("Converting maps...");
+
+        data.maps
+            .par_iter()
+            .filter(|map| {
+                self.filter
+                    .as_ref()
+                    .map(|filter| filter(map.value()))
+                    .unwrap_or(true)
+            })
+            .filter(
+                |map| match self.profile.excludes(&self.mappings, map.value()) {
+                    true => {
+                        if let Ok(mut removed) = removed.lock() {
+                            removed.push(format!("map {}", map.key()));
+                        }
+                        false
+                    }
+                    false => true,
+                },
+            )
+            .filter(|map| {
+                match !self.profile.keep_placeholders && self.profile.is_placeholder(map.value()) {
+                    true => {
+                        println!("Skipping placeholder map {}", map.key());
+                        if let Ok(mut removed) = removed.lock() {
+                            removed.push(format!("placeholder map {}", map.key()));
+                        }
+                        false
+                    }
+                    false => true,
+                }
+            })
+            .for_each(|map| {
+                let json = map.value();
+                let map_id = &json.data.id;
+                println!("Converting {}", json.data.name);
+                if let Some((mut map, mut meta)) = convert::into_world_map(
+                    source,
+                    &self.mappings,
+                    &data,
+                    &encounters,
+                    &diagnostics,
+                    json,
+                ) {
+                    if self.fuzzy && !self.mappings.map.name.contains_key(&json.data.name) {
+                        if let Some((key, name)) = self
+                            .mappings
+                            .fuzzy_map_name(&json.data.name, |key| parsed.contains(key))
+                        {
+                            diagnostics.warn(
+                                map_id,
+                                format!(
+                                    "Using map name mapping {} for {} ({})",
+                                    key, json.data.name, name
+                                ),
+                            );
+                            map.name = name.to_owned();
+                        }
+                    }
+                    if !self.profile.exclude_npc_groups.is_empty() {
+                        let groups = &self.profile.exclude_npc_groups;
+                        let mut ids = map
+                            .npcs
+                            .iter()
+                            .filter(|(.., npc)| groups.contains(&npc.group))
+                            .map(|(id, ..)| *id)
+                            .collect::<Vec<_>>();
+                        ids.sort();
+                        if let Ok(mut removed) = removed.lock() {
+                            for id in ids {
+                                map.npcs.remove(&id);
+                                meta.npcs.remove(&id);
+                                removed.push(format!("npc {} in {}", id, map_id));
+                            }
+                        }
+                    }
+                    if !hidden.is_empty() {
+                        let mut ids = meta
+                            .npcs
+                            .iter()
+                            .filter(|(.., npc)| {
+                                npc.flag
+                                    .as_ref()
+                                    .map(|flag| hidden.contains(flag))
+                                    .unwrap_or_default()
+                            })
+                            .map(|(id, ..)| *id)
+                            .collect::<Vec<_>>();
+                        ids.sort();
+                        if let Ok(mut removed) = removed.lock() {
+                            for id in ids {
+                                map.npcs.remove(&id);
+                                meta.npcs.remove(&id);
+                                removed.push(format!("hidden npc {} in {}", id, map_id));
+                            }
+                        }
+                    }
+                    if self.localize {
+                        names.insert(
+                            json.data.name.clone(),
+                            std::mem::replace(&mut map.name, json.data.name.clone()),
+                        );
+                    }
+                    for hook in self.hooks.iter() {
+                        hook(&mut map);
+                    }
+                    metadata.insert(map.id, meta);
+                    // generated_locations rules out collisions, but hooks may still change ids
+                    if let Some(removed) = new_maps.insert(map.id, map) {
+                        diagnostics.warn(
+                            map_id,
+                            format!("Replaced the world map {:?} with the same id", removed.id),
+                        );
+                    }
+                } else {
+                    diagnostics.warn(map_id, "Could not convert into a world map");
+                }
+            });
+
+        convert::face_arrivals(&new_maps, &metadata);
+        convert::mark_music_changes(&new_maps, &metadata);
+
+        println!("Editing maps...");
+
+        self.edits.process(&new_maps, &metadata);
+
+        println!("Done!");
+
+        let spawn = self.spawn.clone().unwrap_or_default();
+        let location = convert::location(&self.mappings, &data, &spawn.map);
+        let spawn = match new_maps.contains_key(&location) {
+            true => Some(WarpDestination {
+                location,
+                position: Destination {
+                    coords: Coordinate {
+                        x: spawn.x as _,
+                        y: spawn.y as _,
+                    },
+                    direction: None,
+                },
+            }),
+            false => {
+                // only a chosen spawn is worth reporting, as filters often leave out the default one
+                if self.spawn.is_some() {
+                    diagnostics.warn(&spawn.map, "Spawn map was not converted");
+                }
+                None
+            }
+        };
+
+        let new_maps = new_maps.into_par_iter().collect();
+
+        let heal_locations =
+            convert::into_heal_locations(&self.mappings, &data, &diagnostics, &new_maps);
+
+        let diagnostics = diagnostics.into_inner();
+
+        if self.strict && !diagnostics.is_empty() {
+            anyhow::bail!(
+                "Conversion reported {} diagnostics in strict mode",
+                diagnostics.len()
+            );
+        }
+
+        Ok(Conversion {
+            world: WorldData {
+                heal_locations,
+                maps: new_maps,
+                spawn,
+                names: names.into_iter().collect(),
+                metadata: metadata.into_par_iter().collect(),
+                scripts: convert::create_world_script_data(
+                    &self.mappings,
+                    &data.scripts,
+                    &data.messages,
+                ),
+                raw_scripts: convert::create_raw_scripts(&self.mappings, &data.scripts),
+            },
+            diagnostics,
+            removed: {
+                let mut removed = removed.into_inner().unwrap_or_default();
+                removed.sort();
+                removed
+            },
+            generated,
+        })
+    }
+}
@@ -13,9 +13,14 @@ use firecore_world_builder::{
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize)]
+use crate::{MapMetadata, Outbreak, Schedule};
+
+#[derive(Default, Deserialize, Serialize)]
 pub struct Edits {
     pub maps: HashMap<BuilderLocation, MapEdits>,
+    /// Conditions for NPCs to appear, keyed by the pret symbol of their script
+    #[serde(default)]
+    pub schedules: HashMap<String, Schedule>,
 }
 
 #[derive(Default, Deserialize, Serialize)]
@@ -24,6 +29,8 @@ pub struct MapEdits {
     pub npcs: Vec<NpcEdits>,
     #[serde(default)]
     pub warps: Vec<WarpEdits>,
+    #[serde(default)]
+    pub outbreaks: Vec<Outbreak>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -58,14 +65,31 @@ impl Edits {
     //     }
     // }
 
-    pub fn process(self, maps: &DashMap<Location, WorldMap>) {
-        let edits = self
+    pub fn process(
+        self,
+        maps: &DashMap<Location, WorldMap>,
+        metadata: &DashMap<Location, MapMetadata>,
+    ) {
+        let mut edits = self
             .maps
             .into_iter()
             .map(|(k, v)| (k.into(), v))
             .collect::<HashMap<Location, MapEdits>>();
+        if !self.schedules.is_empty() {
+            for mut meta in metadata.iter_mut() {
+                for npc in meta.npcs.values_mut() {
+                    if let Some(schedule) = npc
+                        .script
+                        .as_deref()
+                        .and_then(|script| self.schedules.get(script))
+                    {
+                        npc.schedule = Some(schedule.clone());
+                    }
+                }
+            }
+        }
         for mut map in maps.iter_mut() {
-            if let Some(edit) = edits.get(map.key()) {
+            if let Some(edit) = edits.remove(map.key()) {
                 for npc in &edit.npcs {
                     match npc {
                         NpcEdits::Remove(id) => {
@@ -93,6 +117,13 @@ impl Edits {
                         }
                     }
                 }
+                if !edit.outbreaks.is_empty() {
+                    metadata
+                        .entry(*map.key())
+                        .or_default()
+                        .outbreaks
+                        .extend(edit.outbreaks);
+                }
             }
         }
     }
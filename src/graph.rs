@@ -0,0 +1,336 @@
+//! Graphs of how converted maps lead into each other, for checking them and
+//! for viewing them with Graphviz.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::Path,
+};
+
+use firecore_world_builder::world::{
+    map::{warp::WarpDestination, WorldMap},
+    positions::{Coordinate, CoordinateInt, Direction, Location},
+};
+use hashbrown::{HashMap, HashSet};
+
+use crate::{output, MapMetadata};
+
+/// Reads every map written to a directory, either as `.world` files or by
+/// [`write_split`](output::write_split).
+pub fn read_maps(dir: &Path) -> anyhow::Result<HashMap<Location, WorldMap>> {
+    let mut maps = HashMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let extension = path.extension().and_then(|e| e.to_str());
+        let index = path.file_stem().and_then(|s| s.to_str()) == Some("index");
+        if matches!(extension, Some("bin" | "world")) && !index {
+            let map = output::from_bytes::<WorldMap>(&std::fs::read(&path)?)
+                .map_err(|err| anyhow::anyhow!("{:?}: {}", path, err))?;
+            maps.insert(map.id, map);
+        }
+    }
+    Ok(maps)
+}
+
+/// Reads the location of the spawn written next to converted maps, if there is one.
+pub fn read_spawn(path: &Path) -> Option<Location> {
+    let spawn = std::fs::read(path).ok()?;
+    serde_json::from_slice::<Option<WarpDestination>>(&spawn)
+        .ok()
+        .flatten()
+        .map(|spawn| spawn.location)
+}
+
+/// Name of a map in graphs, which is its file name without an extension.
+pub fn node(location: &Location) -> String {
+    output::file_name(location, "")
+        .trim_end_matches('.')
+        .to_owned()
+}
+
+/// Finds the maps that cannot be reached from `start` by taking warps and walking across connections.
+///
+/// Dynamic warps are left to the engine, so maps only entered through them are reported too.
+pub fn unreachable(maps: &HashMap<Location, WorldMap>, start: &Location) -> Vec<Location> {
+    let mut reached = HashSet::new();
+    let mut queue = vec![*start];
+
+    while let Some(location) = queue.pop() {
+        if !reached.insert(location) {
+            continue;
+        }
+        let map = match maps.get(&location) {
+            Some(map) => map,
+            None => continue,
+        };
+        queue.extend(map.warps.iter().map(|warp| warp.destination.location));
+        queue.extend(
+            map.chunk
+                .iter()
+                .flat_map(|chunk| chunk.connections.iter())
+                .flat_map(|(.., connections)| connections.iter().map(|connection| connection.0)),
+        );
+    }
+
+    let mut unreachable = maps
+        .keys()
+        .filter(|location| !reached.contains(*location))
+        .copied()
+        .collect::<Vec<_>>();
+    unreachable.sort_by_key(node);
+    unreachable
+}
+
+/// Warp whose destination has no warp leading back to its map.
+#[derive(Debug, Clone, Copy)]
+pub struct OneWayWarp {
+    pub map: Location,
+    pub coords: Coordinate,
+    pub destination: Location,
+}
+
+/// Finds warps that land on a tile with no warp back to the map they came from,
+/// which usually means a warp was dropped or mapped to the wrong map.
+///
+/// Warps meant to be [one way](crate::WarpMetadata::one_way), such as holes, are left out,
+/// as are warps landing on a dynamic warp, which can lead anywhere.
+/// Ledges are not warps, so they never show up here.
+pub fn one_way_warps(
+    maps: &HashMap<Location, WorldMap>,
+    metadata: &HashMap<Location, MapMetadata>,
+) -> Vec<OneWayWarp> {
+    let mut one_way = Vec::new();
+
+    for (location, map) in maps.iter() {
+        let meta = metadata.get(location);
+        for warp in map.warps.iter() {
+            let coords = warp.area.min;
+            let intended = meta
+                .map(|meta| {
+                    meta.warps
+                        .iter()
+                        .any(|warp| warp.one_way && warp.coords == coords)
+                })
+                .unwrap_or_default();
+            if intended {
+                continue;
+            }
+
+            let destination = warp.destination.location;
+            let landing = warp.destination.position.coords;
+            let dynamic = metadata
+                .get(&destination)
+                .map(|meta| meta.dynamic_warps.iter().any(|warp| warp.coords == landing))
+                .unwrap_or_default();
+            let returns = maps
+                .get(&destination)
+                .map(|map| {
+                    map.warps.iter().any(|warp| {
+                        warp.destination.location == *location
+                            && (warp.area.min.x..=warp.area.max.x).contains(&landing.x)
+                            && (warp.area.min.y..=warp.area.max.y).contains(&landing.y)
+                    })
+                })
+                .unwrap_or_default();
+
+            if !dynamic && !returns {
+                one_way.push(OneWayWarp {
+                    map: *location,
+                    coords,
+                    destination,
+                });
+            }
+        }
+    }
+
+    one_way.sort_by_key(|warp| (node(&warp.map), warp.coords.y, warp.coords.x));
+    one_way
+}
+
+/// Connection whose neighbor does not connect back along the same edge.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionMismatch {
+    pub map: Location,
+    pub direction: Direction,
+    pub neighbor: Location,
+    pub offset: CoordinateInt,
+    /// Offset the neighbor connects back with, if it connects back at all
+    pub back: Option<CoordinateInt>,
+}
+
+/// Finds connections that are not mirrored by their neighbor, which show up as seams in the engine.
+///
+/// A map connecting right to a neighbor at offset `k` should have that neighbor
+/// connect left back to it at offset `-k`, and likewise for the other directions.
+pub fn connection_mismatches(maps: &HashMap<Location, WorldMap>) -> Vec<ConnectionMismatch> {
+    let connections = |location: &Location| {
+        maps.get(location)
+            .into_iter()
+            .flat_map(|map| map.chunk.iter())
+            .flat_map(|chunk| chunk.connections.iter())
+            .flat_map(|(direction, connections)| {
+                connections.iter().map(move |connection| {
+                    (*direction, connection.0, connection.1 as CoordinateInt)
+                })
+            })
+    };
+
+    let mut mismatches = Vec::new();
+
+    for location in maps.keys() {
+        for (direction, neighbor, offset) in connections(location) {
+            let opposite = match direction {
+                Direction::Up => Direction::Down,
+                Direction::Down => Direction::Up,
+                Direction::Left => Direction::Right,
+                Direction::Right => Direction::Left,
+            };
+            let back = connections(&neighbor)
+                .filter(|(direction, map, ..)| *direction == opposite && map == location)
+                .map(|(.., offset)| offset)
+                .collect::<Vec<_>>();
+            if !back.contains(&-offset) {
+                mismatches.push(ConnectionMismatch {
+                    map: *location,
+                    direction,
+                    neighbor,
+                    offset,
+                    back: back.first().copied(),
+                });
+            }
+        }
+    }
+
+    mismatches.sort_by_key(|mismatch| (node(&mismatch.map), node(&mismatch.neighbor)));
+    mismatches
+}
+
+/// Writes maps as nodes, with solid edges for warps and dashed edges for connections.
+///
+/// Maps that warp into each other more than once share one edge.
+pub fn dot(maps: &HashMap<Location, WorldMap>) -> String {
+    let nodes = maps
+        .iter()
+        .map(|(location, map)| (node(location), map.name.as_str()))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut warps = BTreeSet::new();
+    let mut connections = BTreeSet::new();
+
+    for (location, map) in maps.iter() {
+        for warp in map.warps.iter() {
+            warps.insert((node(location), node(&warp.destination.location)));
+        }
+        for (direction, neighbors) in map.chunk.iter().flat_map(|chunk| chunk.connections.iter()) {
+            for connection in neighbors.iter() {
+                connections.insert((
+                    node(location),
+                    node(&connection.0),
+                    format!("{:?}", direction),
+                ));
+            }
+        }
+    }
+
+    let mut graph = String::from("digraph world {\n    node [shape = box];\n");
+    for (id, name) in nodes.iter() {
+        writeln!(graph, "    {:?} [label = {:?}];", id, name).ok();
+    }
+    for (from, to) in warps.iter() {
+        writeln!(graph, "    {:?} -> {:?};", from, to).ok();
+    }
+    for (from, to, direction) in connections.iter() {
+        writeln!(
+            graph,
+            "    {:?} -> {:?} [style = dashed, label = {:?}];",
+            from, to, direction
+        )
+        .ok();
+    }
+    graph.push_str("}\n");
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use firecore_world_builder::world::{
+        map::{
+            chunk::{ChunkConnections, Connection, WorldChunk},
+            warp::WarpEntry,
+        },
+        positions::{BoundingBox, Destination},
+    };
+
+    use super::*;
+    use crate::builder::WorldMapBuilder;
+
+    fn location(name: &str) -> Location {
+        Location {
+            map: None,
+            index: name.parse().unwrap(),
+        }
+    }
+
+    fn warp(x: CoordinateInt, y: CoordinateInt, to: &str, landing: Coordinate) -> WarpEntry {
+        let coords = Coordinate { x, y };
+        WarpEntry {
+            area: BoundingBox {
+                min: coords,
+                max: coords,
+            },
+            destination: WarpDestination {
+                location: location(to),
+                position: Destination {
+                    coords: landing,
+                    direction: None,
+                },
+            },
+        }
+    }
+
+    /// Pallet Town warps into a house and back, into a cave with no way out
+    /// and connects up to Route 1, while an island is only reachable by boat.
+    fn world() -> HashMap<Location, WorldMap> {
+        let mut connections = ChunkConnections::new();
+        connections.insert(Direction::Up, vec![Connection(location("route1"), 0)]);
+        [
+            WorldMapBuilder::new(location("pallet"), "Pallet Town", 4, 4)
+                .warps(vec![
+                    warp(1, 1, "house", Coordinate { x: 2, y: 3 }),
+                    warp(3, 3, "cave", Coordinate { x: 0, y: 0 }),
+                ])
+                .chunk(Some(WorldChunk { connections })),
+            WorldMapBuilder::new(location("house"), "House", 4, 4).warps(vec![warp(
+                2,
+                3,
+                "pallet",
+                Coordinate { x: 1, y: 1 },
+            )]),
+            WorldMapBuilder::new(location("cave"), "Cave", 4, 4),
+            WorldMapBuilder::new(location("route1"), "Route 1", 4, 4),
+            WorldMapBuilder::new(location("island"), "Island", 4, 4),
+        ]
+        .into_iter()
+        .map(|builder| {
+            let map = builder.build().unwrap();
+            (map.id, map)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn finds_maps_unreachable_from_the_start() {
+        let unreachable = unreachable(&world(), &location("pallet"));
+        assert_eq!(unreachable.iter().map(node).collect::<Vec<_>>(), ["island"]);
+    }
+
+    #[test]
+    fn finds_warps_without_a_warp_back() {
+        let one_way = one_way_warps(&world(), &HashMap::new());
+        assert_eq!(one_way.len(), 1);
+        let warp = one_way[0];
+        assert_eq!(node(&warp.map), "pallet");
+        assert_eq!((warp.coords.x, warp.coords.y), (3, 3));
+        assert_eq!(node(&warp.destination), "cave");
+    }
+}
@@ -0,0 +1,47 @@
+//! HTTP client shared by every download, so connections are reused between requests.
+
+use std::{io::Read, sync::OnceLock, time::Duration};
+
+static CLIENT: OnceLock<ureq::Agent> = OnceLock::new();
+
+/// Idle connections kept open to each host, enough for every rayon thread to reuse one.
+const IDLE_CONNECTIONS: usize = 64;
+
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest time a single request may take, including reading its body.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Largest file downloaded when no smaller limit is given.
+pub const MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+pub fn client() -> &'static ureq::Agent {
+    CLIENT.get_or_init(|| {
+        ureq::AgentBuilder::new()
+            .user_agent(concat!("firecore-world-gen/", env!("CARGO_PKG_VERSION")))
+            .max_idle_connections(IDLE_CONNECTIONS)
+            .max_idle_connections_per_host(IDLE_CONNECTIONS)
+            .timeout_connect(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+    })
+}
+
+/// Downloads a file, failing on error statuses.
+pub fn get(url: &str) -> anyhow::Result<Vec<u8>> {
+    get_limited(url, MAX_SIZE)
+}
+
+/// Downloads a file, failing if it is larger than `limit` bytes.
+pub fn get_limited(url: &str, limit: u64) -> anyhow::Result<Vec<u8>> {
+    let response = client().get(url).call()?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(limit + 1)
+        .read_to_end(&mut bytes)?;
+    match bytes.len() as u64 > limit {
+        true => Err(anyhow::anyhow!("{} is larger than {} bytes", url, limit)),
+        false => Ok(bytes),
+    }
+}
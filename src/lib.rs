@@ -1,163 +1,131 @@
-use std::{sync::Arc, num::ParseIntError};
+use std::sync::Arc;
 
 use dashmap::DashMap;
 use hashbrown::{hash_map::DefaultHashBuilder as RandomState, HashMap};
 
-use firecore_world_builder::{
-    bin::BinaryMap,
-    world::{
-        character::{
-            npc::{
-                group::TrainerGroupId,
-                trainer::{NpcTrainer, TrainerDisable},
-                Npc, NpcInteract, NpcMovement, Npcs,
-            },
-            trainer::Trainer,
-            Character,
-        },
-        map::{
-            chunk::{ChunkConnections, Connection, WorldChunk},
-            movement::Elevation,
-            object::{ItemObject, Items, MapObject, Objects, SignObject, Signs},
-            warp::{WarpDestination, WarpEntry},
-            wild::{WildEntry, WildType},
-            Brightness, PaletteId, WorldMap, WorldMapSettings, WorldTile,
-        },
-        pokedex::{
-            item::{Item, ItemStack},
-            moves::{owned::SavedMove, Move},
-            pokemon::{owned::SavedPokemon, stat::StatSet, Pokemon},
-            BasicDex,
-        },
-        positions::{BoundingBox, Coordinate, Destination, Direction, Location, Position}, script::{WorldScriptData, WorldInstruction, ScriptId},
-    },
-};
-use map::{
-    object::{JsonBgEvent, JsonObjectEvent},
-    warp::JsonWarpEvent,
-    wild::JsonWildEncounters,
-    JsonConnection, JsonMap,
-};
-use rayon::iter::{
-    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+use firecore_world_builder::world::{
+    map::{warp::WarpDestination, WorldMap},
+    pokedex::{item::Item, moves::Move, pokemon::Pokemon, BasicDex},
+    positions::Location,
+    script::WorldScriptData,
 };
-use script_parser::inc::{Script, Command};
+use map::{wild::JsonWildEncounters, JsonMap, JsonMapGroup, JsonMapLayout};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use script_parser::inc::Script;
 use serde_json::Value;
-use tinystr::TinyStr16;
 
-use crate::map::JsonMapLayout;
+pub const REPOSITORY: &str = "http://raw.githubusercontent.com/pret/pokefirered";
 
-const PATH: &str = "http://raw.githubusercontent.com/pret/pokefirered/master";
+/// pret's character encoding, used to decode message text.
+pub const CHARMAP: &str = "charmap.txt";
 
+/// pret's heal locations, which newer revisions keep as JSON instead of a header.
+pub const HEAL_LOCATIONS: (&str, &str) = (
+    "src/data/heal_locations.json",
+    "src/data/heal_locations.h",
+);
+
+/// Headers that may hold pret's in-game trade table, tried in order.
+pub const TRADE_TABLES: [&str; 2] = ["src/data/ingame_trades.h", "src/data/trade.h"];
+
+pub mod assets;
+pub mod behavior;
+pub mod builder;
+pub mod cache;
+pub mod codegen;
+pub mod config;
+pub mod convert;
+pub mod converter;
 mod edits;
-mod map;
+pub mod graph;
+pub mod http;
+pub mod map;
 mod mapping;
+mod metadata;
+pub mod output;
+pub mod progression;
+pub mod prompt;
+pub mod registry;
+pub mod scaffold;
+pub mod serve;
+pub mod source;
+pub mod tileset;
 
 pub use mapping::*;
 pub use edits::*;
+pub use metadata::*;
+pub use source::Source;
 // mod serializable;
 
-type Maps = DashMap<String, JsonMap, RandomState>;
-type Scripts = DashMap<String, Script, RandomState>;
-type Messages = DashMap<String, Vec<Vec<String>>, RandomState>;
-type Trainers = HashMap<String, script_parser::trainer::Trainer>;
-type Parties = HashMap<String, Vec<script_parser::trainer::party::TrainerPokemon>>;
+pub type Maps = DashMap<String, JsonMap, RandomState>;
+pub type Scripts = DashMap<String, Script, RandomState>;
+pub type ScriptMaps = DashMap<String, String, RandomState>;
+pub type Messages = DashMap<String, Vec<Vec<String>>, RandomState>;
+pub type Trainers = HashMap<String, script_parser::trainer::Trainer>;
+pub type Parties = HashMap<String, Vec<script_parser::trainer::party::TrainerPokemon>>;
+pub type Trades = HashMap<String, script_parser::trade::InGameTrade>;
+pub type HealLocations = Vec<script_parser::heal::HealLocation>;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ParsedData {
     pub maps: Maps,
+    /// Map groups in pret's `group_order`
+    pub groups: Vec<JsonMapGroup>,
     pub wild: JsonWildEncounters,
     pub pokedex: BasicDex<Pokemon, Arc<Pokemon>>,
     pub movedex: BasicDex<Move, Arc<Move>>,
     pub itemdex: BasicDex<Item, Arc<Item>>,
     pub scripts: Scripts,
+    /// Name of the map whose `scripts.inc` each script label was read from
+    pub script_maps: ScriptMaps,
     pub messages: Messages,
     pub trainers: Trainers,
     pub parties: Parties,
+    pub trades: Trades,
+    pub heal_locations: HealLocations,
+    pub behaviors: behavior::TilesetBehaviors,
+    pub tileset_dirs: tileset::TilesetDirs,
+}
+
+impl ParsedData {
+    /// Whether a script was read from the `scripts.inc` of a map, rather than
+    /// one of the maps named after it such as its interiors.
+    pub fn is_map_script(&self, script: &str, map: &str) -> bool {
+        self.script_maps
+            .get(script)
+            .map(|source| source.value() == map)
+            .unwrap_or_default()
+    }
 }
 
 pub struct WorldData {
     pub maps: HashMap<Location, WorldMap>,
+    pub metadata: HashMap<Location, MapMetadata>,
     pub scripts: WorldScriptData,
+    /// Scripts with commands worldlib cannot run, keyed by their pret label
+    pub raw_scripts: std::collections::BTreeMap<String, Script>,
+    /// Places to fly to and respawn at, in pret's order
+    pub heal_locations: Vec<HealLocation>,
+    /// Where new games start, if its map was converted
+    pub spawn: Option<WarpDestination>,
+    /// Display names of the maps keyed by pret map name, if they were
+    /// [localized](converter::ConverterBuilder::localize)
+    pub names: std::collections::BTreeMap<String, String>,
 }
 
+/// Converts with the default [`Converter`](converter::Converter) options.
 pub fn compile(
+    source: &dyn Source,
     mappings: NameMappings,
     edits: edits::Edits,
-    mut data: ParsedData,
+    data: ParsedData,
 ) -> anyhow::Result<WorldData> {
-
-    println!("Converting wild encounters...");
-
-    eprintln!("TODO: fix fishing encounters");
-
-    let encounters = DashMap::new();
-
-    let wild = std::mem::take(&mut data.wild.wild_encounter_groups);
-
-    wild.into_par_iter()
-        .flat_map(|g| g.encounters.into_par_iter())
-        .filter(|e| e.base_label[(e.base_label.len() - 7)..].eq_ignore_ascii_case("FireRed"))
-        .for_each(|e| {
-            let mut entries = HashMap::new();
-            if let Some(e) = e.land_mons {
-                entries.insert(WildType::Land, e.into(&data.pokedex));
-            }
-            if let Some(e) = e.water_mons {
-                entries.insert(WildType::Water, e.into(&data.pokedex));
-            }
-            if let Some(e) = e.rock_smash_mons {
-                entries.insert(WildType::Rock, e.into(&data.pokedex));
-            }
-            if let Some(e) = e.fishing_mons {
-                entries.insert(WildType::Fishing(0), e.into(&data.pokedex));
-            }
-            if entries.is_empty() {
-                encounters.insert(e.map, None);
-            } else {
-                encounters.insert(e.map, Some(entries));
-            }
-        });
-
-    println!("Created {} wild encounters", encounters.len());
-
-    let new_maps = DashMap::<Location, WorldMap>::new();
-
-    println!("Converting maps...");
-
-    data.maps.par_iter().for_each(|map| {
-        let map = map.value();
-        println!("Converting {}", map.data.name);
-        if let Some(map) = into_world_map(&mappings, &data, &encounters, map) {
-            if let Some(removed) = new_maps.insert(map.id, map) {
-                panic!("Duplicate world map id {}", removed.id);
-            }
-        } else {
-            eprintln!("Could not convert {} into a world map", map.data.name);
-        }
-    });
-
-    println!("Editing maps...");
-
-    edits.process(&new_maps);
-
-    // println!("Saving maps...");
-
-    // serializable::serialize(
-    //     "output",
-    //     &new_maps,
-    //     &mappings,
-    //     &data.scripts,
-    //     &data.messages,
-    // );
-
-    println!("Done!");
-
-    Ok(WorldData {
-        maps: new_maps.into_par_iter().collect(),
-        scripts: create_world_script_data(&mappings, &data.scripts, &data.messages),
-    })
-
+    Ok(converter::Converter::builder()
+        .mappings(mappings)
+        .edits(edits)
+        .build()
+        .convert(source, data)?
+        .world)
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -173,7 +141,7 @@ impl core::fmt::Display for OptionError {
     }
 }
 
-pub fn create_data() -> anyhow::Result<ParsedData> {
+pub fn create_data(source: &dyn Source) -> anyhow::Result<ParsedData> {
     eprintln!("Parsed map file cannot be read!");
     eprintln!("Generating new parsed map file...");
 
@@ -211,62 +179,92 @@ pub fn create_data() -> anyhow::Result<ParsedData> {
 
     println!("Getting trainers...");
 
-    let trainers = attohttpc::get(format!("{}/src/data/trainers.h", PATH))
-        .send()?
-        .text_utf8()?;
+    let trainers = source.text("src/data/trainers.h")?;
     let trainers = script_parser::trainer::parse_trainers(&trainers)?;
 
     println!("Getting trainer parties...");
 
-    let parties = attohttpc::get(format!("{}/src/data/trainer_parties.h", PATH))
-        .send()?
-        .text_utf8()?;
+    let parties = source.text("src/data/trainer_parties.h")?;
     let parties = script_parser::trainer::party::parse_parties(&parties)?;
 
+    println!("Getting in-game trades...");
+
+    let trades = match TRADE_TABLES.iter().find_map(|path| source.text(path).ok()) {
+        Some(trades) => script_parser::trade::parse_trades(&trades)?,
+        None => {
+            eprintln!("Could not get in-game trade table");
+            Default::default()
+        }
+    };
+
+    println!("Getting heal locations...");
+
+    let heal_locations = match source.text(HEAL_LOCATIONS.0) {
+        Ok(json) => {
+            #[derive(serde::Deserialize)]
+            struct JsonHealLocations {
+                heal_locations: HealLocations,
+            }
+            serde_json::from_str::<JsonHealLocations>(&json)?.heal_locations
+        }
+        Err(..) => match source.text(HEAL_LOCATIONS.1) {
+            Ok(header) => script_parser::heal::parse_heal_locations(&header)?,
+            Err(err) => {
+                eprintln!("Could not get heal locations with error {}", err);
+                Default::default()
+            }
+        },
+    };
+
     println!("Getting layouts...");
 
-    let layouts = attohttpc::get(format!("{}/data/layouts/layouts.json", PATH))
-        .send()?
-        .json::<map::JsonMapLayouts>()?;
+    let layouts = source.layouts()?;
 
     println!("Getting map groups...");
 
-    let maps = attohttpc::get(format!("{}/data/maps/map_groups.json", PATH))
-        .send()?
-        .bytes()?;
+    let maps = source.map_groups()?;
 
     println!("Getting wild encounters...");
 
-    let wild = attohttpc::get(format!("{}/src/data/wild_encounters.json", PATH))
-        .send()?
-        .json::<JsonWildEncounters>()?;
+    let wild = serde_json::from_slice::<JsonWildEncounters>(
+        &source.get("src/data/wild_encounters.json")?,
+    )?;
 
     println!("Parsing map groups...");
 
-    let maps = serde_json::from_slice::<Value>(&maps)?;
-
     let mut names = Vec::new();
 
+    let mut groups = Vec::new();
+
     for group_name in maps
         .get("group_order")
         .ok_or(OptionError::Unknown)?
         .as_array()
         .ok_or(OptionError::Unknown)?
     {
+        let group_name = group_name.as_str().ok_or(OptionError::Unknown)?;
+        let mut group = JsonMapGroup {
+            name: group_name.to_owned(),
+            maps: Vec::new(),
+        };
         for name in maps
-            .get(group_name.as_str().ok_or(OptionError::Unknown)?)
+            .get(group_name)
             .ok_or(OptionError::Unknown)?
             .as_array()
             .ok_or(OptionError::Unknown)?
         {
-            names.push(name.as_str().ok_or(OptionError::Unknown)?);
+            let name = name.as_str().ok_or(OptionError::Unknown)?;
+            names.push(name);
+            group.maps.push(name.to_owned());
         }
+        groups.push(group);
     }
 
     println!("Found {} map names", names.len());
 
     let maps: Maps = Default::default();
     let mut scripts: Scripts = Default::default();
+    let script_maps: ScriptMaps = Default::default();
     let messages: Messages = Default::default();
 
     let layouts = layouts
@@ -277,25 +275,34 @@ pub fn create_data() -> anyhow::Result<ParsedData> {
         .collect::<DashMap<String, JsonMapLayout, RandomState>>();
 
     names.into_par_iter().for_each(|map| {
-        let path = format!("{}/data/maps/{}/map.json", PATH, map);
-        let scripts_path = format!("{}/data/maps/{}/scripts.inc", PATH, map);
-        let text_path = format!("{}/data/maps/{}/text.inc", PATH, map);
-
-        let data = attohttpc::get(path)
-            .send()
-            .unwrap_or_else(|err| panic!("Could not get {} with error {}", map, err))
-            .json::<map::JsonMapData>()
-            .unwrap_or_else(|err| panic!("Could not get {} with error {}", map, err));
-
-        if let Some(scripts_data) = attohttpc::get(scripts_path)
-            .send()
-            .ok()
-            .map(|r| r.text().ok())
-            .flatten()
-        {
+        // a map that fails to download is skipped instead of stopping the others
+        let data = match source.map_json(map) {
+            Ok(data) => data,
+            Err(err) => {
+                eprintln!("Skipping map {}, could not get it with error {}", map, err);
+                return;
+            }
+        };
+
+        let layout = match layouts.get(&data.layout) {
+            Some(layout) => layout.value().clone(),
+            None => {
+                eprintln!(
+                    "Skipping map {}, could not get its layout {}",
+                    data.name, data.layout
+                );
+                return;
+            }
+        };
+
+        if let Ok(scripts_data) = source.map_scripts(map) {
+            for message in script_parser::inc::parse_strings(&scripts_data) {
+                messages.entry(message.name).or_insert(message.text);
+            }
             match script_parser::inc::parse(&scripts_data) {
                 Ok(scripts_data) => {
                     for script in scripts_data {
+                        script_maps.insert(script.name.clone(), data.name.clone());
                         scripts.insert(script.name.clone(), script);
                     }
                 }
@@ -305,12 +312,7 @@ pub fn create_data() -> anyhow::Result<ParsedData> {
             }
         }
 
-        if let Some(message_data) = attohttpc::get(text_path)
-            .send()
-            .ok()
-            .map(|r| r.text().ok())
-            .flatten()
-        {
+        if let Ok(message_data) = source.map_text(map) {
             if let Ok(message_data) = script_parser::inc::parse_message_script(&message_data) {
                 for message in message_data {
                     messages.insert(message.name, message.text);
@@ -318,12 +320,6 @@ pub fn create_data() -> anyhow::Result<ParsedData> {
             }
         }
 
-        let layout = layouts
-            .get(&data.layout)
-            .unwrap_or_else(|| panic!("Could not get map layout {}", data.layout));
-
-        let layout = layout.value().clone();
-
         println!("Parsed map {}", data.name);
 
         if let Some(removed) = maps.insert(data.id.clone(), JsonMap { data, layout }) {
@@ -331,11 +327,48 @@ pub fn create_data() -> anyhow::Result<ParsedData> {
         }
     });
 
+    println!("Decoding messages...");
+
+    match source.text(CHARMAP) {
+        Ok(charmap) => {
+            let charmap = script_parser::charmap::Charmap::parse(&charmap)?;
+            for mut message in messages.iter_mut() {
+                for line in message.value_mut().iter_mut().flatten() {
+                    *line = charmap.decode(line);
+                }
+            }
+        }
+        Err(err) => eprintln!("Could not get charmap with error {}", err),
+    }
+
+    println!("Getting metatile behaviors...");
+
+    let used = maps
+        .iter()
+        .map(|map| map.value().layout.clone())
+        .collect::<Vec<_>>();
+
+    println!("Getting tileset directories...");
+
+    let tileset_dirs = match (
+        source.text(tileset::TILESET_HEADERS),
+        source.text(tileset::TILESET_METATILES),
+    ) {
+        (Ok(headers), Ok(metatiles)) => tileset::parse_tileset_dirs(&headers, &metatiles),
+        (Err(err), ..) | (.., Err(err)) => {
+            eprintln!(
+                "Could not get tileset headers with error {}, finding tilesets by name",
+                err
+            );
+            Default::default()
+        }
+    };
+
+    let behaviors = behavior::TilesetBehaviors::load(source, &tileset_dirs, used.iter())?;
+
     println!("Getting trainer scripts...");
 
-    let trainer_scripts = attohttpc::get(format!("{}/data/scripts/trainers.inc", PATH))
-        .send()?
-        .text_utf8()?;
+    let trainer_scripts = source.text("data/scripts/trainers.inc")?;
 
     println!("Parsing trainer scripts...");
 
@@ -345,16 +378,33 @@ pub fn create_data() -> anyhow::Result<ParsedData> {
             .map(|s| (s.name.clone(), s)),
     );
 
+    println!("Getting item ball scripts...");
+
+    match source.text("data/scripts/item_ball_scripts.inc") {
+        Ok(item_scripts) => scripts.extend(
+            script_parser::inc::parse(&item_scripts)?
+                .into_iter()
+                .map(|s| (s.name.clone(), s)),
+        ),
+        Err(err) => eprintln!("Could not get item ball scripts with error {}", err),
+    }
+
     let data = ParsedData {
         maps,
+        groups,
         wild,
         pokedex,
         movedex,
         itemdex,
         scripts,
+        script_maps,
         messages,
         trainers,
         parties,
+        trades,
+        heal_locations,
+        behaviors,
+        tileset_dirs,
     };
 
     println!("Done parsing maps!");
@@ -370,663 +420,17 @@ pub fn create_data() -> anyhow::Result<ParsedData> {
     Ok(data)
 }
 
-fn into_world_map(
-    mappings: &NameMappings,
-    data: &ParsedData,
-    encounters: &DashMap<String, Option<HashMap<WildType, WildEntry>>>,
-    map: &JsonMap,
-) -> Option<WorldMap> {
-    let map_path = format!("{}/{}", PATH, map.layout.blockdata_filepath);
-    let border_path = format!("{}/{}", PATH, map.layout.border_filepath);
-
-    let map_data = attohttpc::get(map_path).send().unwrap().bytes().unwrap();
-    let border_data = attohttpc::get(border_path).send().unwrap().bytes().unwrap();
-
-    let mapdata = BinaryMap::load(
-        &map_data,
-        &border_data,
-        map.layout.width * map.layout.height,
-    )?;
-
-    let palettes = into_palettes(
-        mappings,
-        &map.layout.primary_tileset,
-        &map.layout.secondary_tileset,
-    );
-
-    let id = mappings
-        .map
-        .id
-        .get(&map.data.id)
-        .cloned()
-        .unwrap_or_else(|| loc(&map.data.id));
-
-    let border = mapdata
-        .border
-        .tiles
-        .into_iter()
-        .map(|tile| {
-            let size = *mappings.palettes.sizes.get(&palettes[0]).unwrap();
-            match size > tile {
-                false => WorldTile::Secondary(tile - size),
-                true => WorldTile::Primary(tile),
-            }
-        })
-        .collect::<Vec<_>>();
-
-    Some(WorldMap {
-        id,
-        name: mappings
-            .map
-            .name
-            .get(&map.data.name)
-            .unwrap_or(&map.data.name)
-            // .unwrap_or_else(|| panic!("Cannot get map name mapping for {}", map.data.name))
-            .clone(),
-        music: into_music(mappings, &map.data.music),
-        width: map.layout.width as _,
-        height: map.layout.height as _,
-        tiles: mapdata
-            .tiles
-            .into_iter()
-            .map(|tile| {
-                let palette = *mappings.palettes.sizes.get(&palettes[0]).unwrap();
-                match palette > tile {
-                    false => WorldTile::Secondary(tile - palette),
-                    true => WorldTile::Primary(tile),
-                }
-            })
-            .collect(),
-        palettes,
-        movements: mapdata.movements,
-        border: [border[0], border[1], border[2], border[3]],
-        chunk: map
-            .data
-            .connections
-            .as_ref()
-            .map(|connections| into_chunk(mappings, connections))
-            .flatten(),
-        warps: map
-            .data
-            .warp_events
-            .iter()
-            .flat_map(|warp| into_world_warp(mappings, &data.maps, warp))
-            .collect(),
-        wild: encounters.remove(&map.data.id).map(|(.., v)| v).flatten(),
-        npcs: into_world_npcs(mappings, data, &map.data.object_events),
-        objects: into_world_objects(mappings, &map.data.object_events),
-        items: into_world_items(data, &map.data.bg_events),
-        signs: into_world_signs(data, &map.data.bg_events),
-        settings: WorldMapSettings {
-            fly_position: None,
-            brightness: match map.data.weather == "WEATHER_SHADE" {
-                true => Brightness::Night,
-                false => Brightness::Day,
-            },
-            transition: mappings
-                .map
-                .transition
-                .get(&map.data.battle_scene)
-                .copied()
-                .unwrap_or_else(|| WorldMapSettings::default_transition()),
-        },
-        // scripts: Default::default(),
-    })
-}
-
-fn create_world_script_data(mappings: &NameMappings, scripts: &Scripts, messages: &Messages) -> WorldScriptData {
-    WorldScriptData {
-        scripts: scripts
-            .par_iter()
-            .flat_map(|r| {
-                let k = r.key();
-                let k = k.clone();
-                let v = r.value();
-                let args: Vec<_> = v
-                    .commands
-                    .iter()
-                    .map(|c| match into_instruction(mappings, &k, c) {
-                        Ok(i) => Some(i),
-                        Err(err) => match err {
-                            InstructionError::Unknown(..) => None,
-                            InstructionError::ParseInt(..) => None,
-                            err => panic!("{}", err),
-                        },
-                    })
-                    .collect();
-                if args.contains(&None) {
-                    return None;
-                }
-                (!args.is_empty()).then(|| (k, args.into_iter().flatten().collect()))
-            })
-            .collect(),
-        messages: messages
-            .par_iter()
-            .map(|r| (r.key().clone(), r.value().clone()))
-            .collect(),
-    }
-}
-
-fn into_instruction(
-    mappings: &crate::NameMappings,
-    id: &ScriptId,
-    command: &Command,
-) -> Result<WorldInstruction, InstructionError> {
-    Ok(match command.command.as_str() {
-        "end" => WorldInstruction::End,
-        "return" => WorldInstruction::Return,
-        // set variables
-        "setvar" => WorldInstruction::SetVar(
-            command.arguments[0].clone(),
-            command.arguments[1].parse().map_err(|err| {
-                InstructionError::ParseInt(id.clone(), command.arguments[1].clone(), err)
-            })?,
-        ),
-        "setflag" => WorldInstruction::SetFlag(command.arguments[0].clone()),
-        "specialvar" => {
-            WorldInstruction::SpecialVar(command.arguments[0].clone(), command.arguments[1].clone())
-        }
-        // compare
-        "compare" => WorldInstruction::Compare(
-            command.arguments[0].clone(),
-            match command.arguments[1].as_str() {
-                "TRUE" => 1,
-                "FALSE" => 0,
-                other => other.parse().map_err(|err| {
-                    InstructionError::ParseInt(id.clone(), command.arguments[1].clone(), err)
-                })?,
-            },
-        ),
-        "call" => WorldInstruction::Call(command.arguments[0].clone()),
-        // goto/call
-        "goto_if_eq" => WorldInstruction::GotoIfEq(command.arguments[0].clone()),
-        "goto_if_set" => {
-            WorldInstruction::GotoIfSet(command.arguments[0].clone(), command.arguments[1].clone())
-        }
-        // Player Freezing
-        "lock" => WorldInstruction::Lock,
-        "release" => WorldInstruction::Release,
-        // NPC commands
-        "faceplayer" => WorldInstruction::FacePlayer,
-        "walk_down" => WorldInstruction::Walk(Direction::Down),
-        "walk_up" => WorldInstruction::Walk(Direction::Up),
-        "walk_left" => WorldInstruction::Walk(Direction::Left),
-        "walk_right" => WorldInstruction::Walk(Direction::Right),
-        // Singular trainer battle
-        "trainerbattle_single" => WorldInstruction::TrainerBattleSingle,
-        // Message
-        "msgbox" => WorldInstruction::Msgbox(
-            command.arguments[0].clone(),
-            command.arguments.get(1).cloned(),
-        ),
-        "textcolor" => WorldInstruction::TextColor(command.arguments[0].parse().unwrap()),
-        "message" => WorldInstruction::Message(command.arguments[0].clone()),
-        "waitmessage" => WorldInstruction::WaitMessage,
-        // Sound
-        "playfanfare" => {
-            let (id, var) = mappings
-                .audio
-                .sounds
-                .get(&command.arguments[0][4..])
-                .ok_or(InstructionError::MissingMapping(
-                    id.clone(),
-                    command.arguments[0][4..].to_owned(),
-                ))?;
-            WorldInstruction::PlayFanfare(*id, *var)
-        }
-        "waitfanfare" => WorldInstruction::WaitMessage,
-        // Item
-        "additem" => WorldInstruction::AddItem(command.arguments[0][5..].parse().unwrap()),
-        "checkitemspace" => WorldInstruction::CheckItemSpace(
-            command.arguments[0].clone(),
-            command.arguments[1].parse().unwrap(),
-        ),
-        "getitemname" => WorldInstruction::GetItemName(
-            command.arguments[0].parse().map_err(|err| {
-                InstructionError::ParseInt(id.clone(), command.arguments[0].clone(), err)
-            })?,
-            command.arguments[1].clone(),
-        ),
-        com => return Err(InstructionError::Unknown(id.to_string(), com.to_owned())),
-    })
-}
-
-#[derive(Debug)]
-enum InstructionError {
-    Unknown(ScriptId, String),
-    ParseInt(ScriptId, String, ParseIntError),
-    ParseStr(ScriptId, String, tinystr::TinyStrError),
-    MissingMapping(ScriptId, String),
-}
-
-impl std::error::Error for InstructionError {}
-
-impl std::fmt::Display for InstructionError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
-    }
-}
-
-fn loc(id: &str) -> Location {
-    Location {
-        map: Some("unnamed".parse().unwrap()),
-        index: truncate_id(id),
-    }
-}
-
-fn truncate_id(id: &str) -> TinyStr16 {
-    let id = &id[4..];
-    if id.len() >= 16 {
-        format!("{}{}", &id[..12], &id[id.len() - 4..]).parse()
-    } else {
-        id.parse()
-    }
-    .unwrap()
-}
-
-fn into_chunk(mappings: &NameMappings, json_connections: &[JsonConnection]) -> Option<WorldChunk> {
-    match json_connections.is_empty() {
-        true => None,
-        false => {
-            let mut connections = ChunkConnections::new();
-            for connection in json_connections {
-                let direction = match connection.direction.as_str() {
-                    "left" => Direction::Left,
-                    "right" => Direction::Right,
-                    "up" => Direction::Up,
-                    "down" => Direction::Down,
-                    _ => unreachable!(),
-                };
-                if !connections.contains_key(&direction) {
-                    connections.insert(direction, Vec::new());
-                }
-                connections.get_mut(&direction).unwrap().push(Connection(
-                    mappings
-                        .map
-                        .id
-                        .get(&connection.map)
-                        .cloned()
-                        .unwrap_or_else(|| loc(&connection.map)),
-                    connection.offset as _,
-                ))
-            }
-            Some(WorldChunk { connections })
-        }
-    }
-}
-
-fn into_world_warp(
-    mappings: &NameMappings,
-    maps: &Maps,
-    warp: &JsonWarpEvent,
-) -> Option<WarpEntry> {
-    let destination = mappings
-        .map
-        .id
-        .get(&warp.destination)
-        .cloned()
-        .unwrap_or_else(|| loc(&warp.destination));
-
-    // let name = format!("warp_{}", index).parse().unwrap();
-
-    let entry = WarpEntry {
-        area: BoundingBox {
-            min: Coordinate {
-                x: warp.x as _,
-                y: warp.y as _,
-            },
-            max: Coordinate {
-                x: warp.x as _,
-                y: warp.y as _,
-            },
-        },
-        destination: WarpDestination {
-            location: destination,
-            position: {
-                let w = &maps
-                    .get(&warp.destination)?
-                    // .unwrap_or_else(|| panic!("Cannot get map at {}", warp.destination))
-                    .data
-                    .warp_events[warp.dest_warp_id as usize];
-                Destination {
-                    coords: Coordinate {
-                        x: w.x as _,
-                        y: w.y as _,
-                    },
-                    direction: None,
-                }
-            },
-            // transition: WarpTransition {
-            //     move_on_exit: false,
-            //     warp_on_tile: true,
-            //     change_music: true,
-            // },
-        },
-    };
-
-    Some(entry)
-}
-
-fn into_world_npcs(mappings: &NameMappings, data: &ParsedData, events: &[JsonObjectEvent]) -> Npcs {
-    events
-        .par_iter()
-        .enumerate()
-        .flat_map(|(index, event)| {
-            if let Some(group) = mappings.npcs.groups.get(&event.graphics_id) {
-                let (movement, directions) = mappings
-                    .npcs
-                    .movement
-                    .get(&event.movement_type)
-                    .cloned()
-                    .unwrap_or_default();
-
-                let mut interact = NpcInteract::Nothing;
-
-                let mut trainer = None;
-                let mut name = String::new();
-
-                if let Some(script) = data.scripts.get(&event.script) {
-                    let script = script.value();
-                    // if script.commands.len() == 1 {
-                    //     let command = &script.commands[0];
-                    //     if &command.command == "msgbox" {
-                    //         let message = data.messages.get(&command.arguments[0]).unwrap();
-                    //         let message = message.value();
-                    //         interact = NpcInteract::Message(message.clone());
-                    //     }
-                    // }
-
-                    if !(event.trainer_type.eq_ignore_ascii_case("TRAINER_TYPE_NONE")) {
-                        if let Some(battle) = script.commands.iter().find(|command| {
-                            command.command.eq_ignore_ascii_case("trainerbattle_single")
-                        }) {
-                            let mut args = battle.arguments.iter();
-                            let id = args.next().unwrap();
-                            let encounter_id = args.next().unwrap();
-                            let defeat_id = args.next().unwrap();
-                            let t = data.trainers.get(id).unwrap();
-                            let party = data
-                                .parties
-                                .get(&t.party)
-                                .unwrap_or_else(|| panic!("Could not get party for {}", id));
-                            let sight = event.trainer_sight_or_berry_tree_id.parse().unwrap();
-                            if let Some(trainer_name) = &t.name {
-                                name = trainer_name.clone();
-                            }
-
-                            fn get_group(t: &script_parser::trainer::Trainer) -> TrainerGroupId {
-                                fn get(
-                                    t: &script_parser::trainer::Trainer,
-                                ) -> Option<TrainerGroupId> {
-                                    let text = t.pic.split_once("TRAINER_PIC_").map(|(.., r)| r)?;
-                                    let split = text.split_once('_')?;
-                                    if split.0.eq_ignore_ascii_case("RS") {
-                                        return None;
-                                    }
-                                    Some(text.to_ascii_lowercase().parse().ok()?)
-                                }
-
-                                get(t).unwrap_or_else(|| "placeholder".parse().unwrap())
-                            }
-
-                            trainer = Some(NpcTrainer {
-                                group: get_group(t),
-                                character: Trainer {
-                                    party: party
-                                        .iter()
-                                        .flat_map(|p| {
-                                            let id = p.species[8..].replace('_', "-");
-                                            data.pokedex
-                                                .try_get_named(&id)
-                                                .map(|pokemon| {
-                                                    let mut saved = SavedPokemon {
-                                                        pokemon: pokemon.id,
-                                                        level: p.level,
-                                                        gender: None,
-                                                        ivs: StatSet::uniform(p.ivs / 6),
-                                                        ..Default::default()
-                                                    };
-                                                    if let Some(item) = &p.item {
-                                                        let id = item[5..].replace('_', " ");
-                                                        if let Some(item) = data
-                                                            .itemdex
-                                                            .try_get_named(&id)
-                                                            .or_else(|| {
-                                                                println!(
-                                                                    "Cannot get item id {}",
-                                                                    id
-                                                                );
-                                                                None
-                                                            })
-                                                        {
-                                                            saved.item = Some(item.id);
-                                                        }
-                                                    }
-                                                    if let Some(moves) = p.moves.as_ref() {
-                                                        for m in moves {
-                                                            let id = m[5..].replace('_', " ");
-                                                            if let Some(m) = data
-                                                                .movedex
-                                                                .try_get_named(&id)
-                                                                .or_else(|| {
-                                                                    if !id.eq_ignore_ascii_case(
-                                                                        "NONE",
-                                                                    ) {
-                                                                        println!(
-                                                                            "Cannot get move id {}",
-                                                                            id
-                                                                        );
-                                                                    }
-                                                                    None
-                                                                })
-                                                            {
-                                                                saved
-                                                                    .moves
-                                                                    .push(SavedMove::from(m.id));
-                                                            }
-                                                        }
-                                                    }
-                                                    saved
-                                                })
-                                                .or_else(|| {
-                                                    println!("Cannot get pokemon id {}", id);
-                                                    None
-                                                })
-                                        })
-                                        .collect(),
-                                    bag: Default::default(), //trainer.items.in,
-                                    worth: 0,
-                                },
-                                sight: match sight == 0 {
-                                    true => None,
-                                    false => Some(sight),
-                                },
-                                encounter: data.messages.get(encounter_id).unwrap().clone(),
-                                defeat: data.messages.get(defeat_id).unwrap().clone(),
-                                badge: None,
-                                disable: TrainerDisable::DisableSelf,
-                            });
-
-                            // if let Some(post) = script
-                            //     .commands
-                            //     .iter()
-                            //     .find(|command| command.command == "msgbox")
-                            // {
-                            //     let id = &post.arguments[0];
-                            //     let message = data.messages.get(id).unwrap();
-                            //     let message = message.value();
-                            //     interact = NpcInteract::Message(message.clone());
-                            // }
-                        }
-                    }
-                }
-
-                if name.is_empty() {
-                    name = format!("NPC {}-{}", event.x, event.y);
-                }
-
-                if matches!(interact, NpcInteract::Nothing) && !event.script.is_empty() {
-                    interact = NpcInteract::Script(event.script.clone());
-                }
-
-                let id = format!("npc_{}", index).parse().unwrap();
-
-                let group = group.parse().unwrap();
-                Some((
-                    id,
-                    Npc {
-                        id,
-                        character: Character::new(
-                            name,
-                            Position {
-                                coords: Coordinate {
-                                    x: event.x as _,
-                                    y: event.y as _,
-                                },
-                                direction: *directions.iter().next().unwrap_or(&Direction::Down),
-                                elevation: Elevation(event.elevation),
-                            },
-                        ),
-                        group,
-                        movement: match movement {
-                            true => {
-                                let empty = directions.len() <= 1;
-                                let mut vec = Vec::with_capacity(1 + if empty { 0 } else { 1 });
-                                vec.push(NpcMovement::Move(Coordinate {
-                                    x: event.movement_range_x as _,
-                                    y: event.movement_range_y as _,
-                                }));
-                                if !empty {
-                                    vec.push(NpcMovement::Look(directions));
-                                }
-                                vec
-                            }
-                            false => match directions.len() <= 1 {
-                                true => Vec::new(),
-                                false => vec![NpcMovement::Look(directions)],
-                            },
-                        },
-                        origin: None,
-                        interact,
-                        trainer,
-                    },
-                ))
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
-fn into_world_objects(mappings: &NameMappings, events: &[JsonObjectEvent]) -> Objects {
-    events
-        .par_iter()
-        .flat_map(
-            |event| match mappings.objects.objects.get(&event.graphics_id) {
-                Some(id) => Some({
-                    (
-                        Coordinate {
-                            x: event.x as _,
-                            y: event.y as _,
-                        },
-                        MapObject { group: *id },
-                    )
-                }),
-                None => None,
-            },
-        )
-        .collect()
-}
-
-fn into_world_items(data: &ParsedData, events: &[JsonBgEvent]) -> Items {
-    events
-        .par_iter()
-        .filter(|event| event.type_ == "hidden_item")
-        .flat_map(|event| {
-            Some((
-                Coordinate {
-                    x: event.x as _,
-                    y: event.y as _,
-                },
-                ItemObject {
-                    item: ItemStack {
-                        item: {
-                            let id = event.item.as_ref()?[5..]
-                                .to_ascii_lowercase()
-                                .parse()
-                                .ok()?;
-                            firecore_world_builder::world::pokedex::Dex::try_get(&data.itemdex, &id)
-                                .or_else(|| {
-                                    if !id.eq_ignore_ascii_case("NONE") {
-                                        println!(
-                                            "Cannot get item id {} for hidden item",
-                                            id.as_str()
-                                        );
-                                    }
-                                    None
-                                })?
-                                .id
-                        },
-                        count: event.quantity?,
-                    },
-                    hidden: event.underfoot?,
-                },
-            ))
-        })
-        .collect()
-}
-
-fn into_world_signs(data: &ParsedData, events: &[JsonBgEvent]) -> Signs {
-    events
-        .par_iter()
-        .filter(|event| event.type_ == "sign")
-        .flat_map(|event| {
-            let script = data.scripts.get(event.script.as_ref()?)?;
-            let msgbox = script
-                .commands
-                .iter()
-                .find(|command| command.command == "msgbox")?;
-            let id = msgbox.arguments.get(0)?;
-            let message = data.messages.get(id)?.clone();
-            Some((
-                Coordinate {
-                    x: event.x as _,
-                    y: event.y as _,
-                },
-                SignObject { message },
-            ))
-        })
-        .collect()
-}
-
-fn into_palettes(mappings: &NameMappings, primary: &str, secondary: &str) -> [PaletteId; 2] {
-    let primary = mappings
-        .palettes
-        .primary
-        .get(primary)
-        .copied()
-        .unwrap_or_else(|| {
-            eprintln!("Unknown primary tileset {}", primary);
-            0
-        });
-    let secondary = mappings
-        .palettes
-        .secondary
-        .get(secondary)
-        .copied()
-        .unwrap_or_else(|| {
-            eprintln!("Unknown secondary tileset {}", secondary);
-            13
-        });
-
-    [primary, secondary]
-}
-
-fn into_music(mappings: &NameMappings, music: &str) -> TinyStr16 {
-    mappings.music.get(music).copied().unwrap_or_else(|| {
-        eprintln!("Cannot find music {}", music);
-        "pallet".parse().unwrap()
-    })
+/// Gets the commit hash of a branch, tag or commit of pret's repository.
+pub fn source_revision(reference: &str) -> anyhow::Result<String> {
+    let commit = serde_json::from_slice::<Value>(&http::get(&format!(
+        "https://api.github.com/repos/pret/pokefirered/commits/{}",
+        reference
+    ))?)?;
+    Ok(commit
+        .get("sha")
+        .and_then(Value::as_str)
+        .ok_or(OptionError::Unknown)?
+        .to_owned())
 }
 
 // #[derive(Debug, Deserialize, Default)]
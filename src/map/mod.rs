@@ -4,6 +4,14 @@ pub mod object;
 pub mod warp;
 pub mod wild;
 
+/// A group from `group_order` in map_groups.json, such as `gMapGroup_TownsAndRoutes`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JsonMapGroup {
+    pub name: String,
+    /// Names of the maps in the group, in order
+    pub maps: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct JsonMap {
     pub data: JsonMapData,
@@ -29,7 +37,8 @@ pub struct JsonMapData {
     pub connections: Option<Vec<JsonConnection>>,
     pub object_events: Vec<object::JsonObjectEvent>,
     pub warp_events: Vec<warp::JsonWarpEvent>,
-    // pub coord_events: Vec<>,
+    #[serde(default)]
+    pub coord_events: Vec<object::JsonCoordEvent>,
     pub bg_events: Vec<object::JsonBgEvent>,
 }
 
@@ -30,4 +30,19 @@ pub struct JsonBgEvent {
     // Sign section
     pub player_facing_dir: Option<String>,
     pub script: Option<String>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JsonCoordEvent {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub x: i32,
+    pub y: i32,
+    pub elevation: u8,
+    // Trigger section
+    pub var: Option<String>,
+    pub var_value: Option<String>,
+    pub script: Option<String>,
+    // Weather section
+    pub weather: Option<String>,
+}
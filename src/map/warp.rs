@@ -1,4 +1,10 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Map id of warps whose destination is decided at runtime.
+pub const MAP_DYNAMIC: &str = "MAP_DYNAMIC";
+
+/// Warp id of warps whose destination position is decided at runtime.
+pub const WARP_ID_DYNAMIC: u8 = 127;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct JsonWarpEvent {
@@ -7,5 +13,37 @@ pub struct JsonWarpEvent {
     pub elevation: u8,
     #[serde(rename = "dest_map")]
     pub destination: String,
+    #[serde(deserialize_with = "warp_id")]
     pub dest_warp_id: u8,
 }
+
+impl JsonWarpEvent {
+    /// Whether the game decides where the warp goes, such as the last map the player was outside on.
+    pub fn is_dynamic(&self) -> bool {
+        self.destination == MAP_DYNAMIC || self.dest_warp_id == WARP_ID_DYNAMIC
+    }
+}
+
+/// Reads a warp id written as a number, or as a string in newer pret revisions.
+///
+/// Binary formats, such as the parsed data cache, always hold a number.
+fn warp_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    if !deserializer.is_human_readable() {
+        return u8::deserialize(deserializer);
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum WarpId {
+        Number(u8),
+        Text(String),
+    }
+
+    match WarpId::deserialize(deserializer)? {
+        WarpId::Number(id) => Ok(id),
+        WarpId::Text(text) => match text.as_str() {
+            "WARP_ID_DYNAMIC" => Ok(WARP_ID_DYNAMIC),
+            text => text.parse().map_err(serde::de::Error::custom),
+        },
+    }
+}
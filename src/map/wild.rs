@@ -1,12 +1,13 @@
 use std::sync::Arc;
 
-use hashbrown::HashMap;
+use dashmap::DashMap;
+use hashbrown::{hash_map::DefaultHashBuilder as RandomState, HashMap};
 
 use firecore_world_builder::world::{
-    map::wild::{WildEntry, WildPokemon},
+    map::wild::{WildEntry, WildPokemon, WildType},
     pokedex::{pokemon::Pokemon, BasicDex},
 };
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -51,46 +52,157 @@ pub struct JsonWildEncounterType {
     pub mons: Vec<JsonWildPokemon>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct JsonWildPokemon {
     pub min_level: u8,
     pub max_level: u8,
     pub species: String,
 }
 
-impl JsonWildEncounterType {
-    pub fn into(self, pokedex: &BasicDex<Pokemon, Arc<Pokemon>>) -> WildEntry {
-        WildEntry {
-            ratio: self.encounter_rate,
-            encounters: self
-                .mons
-                .into_par_iter()
-                .flat_map(|mut p| {
-                    let species = &mut p.species[8..];
-                    unsafe {
-                        let find = '_' as u8;
-                        let replace = '-' as u8;
-                        species.as_bytes_mut().iter_mut().for_each(|u| {
-                            if *u == find {
-                                *u = replace;
-                            }
-                        })
-                    }
-                    pokedex.try_get_named(&species)
-                        .map(|species| WildPokemon {
-                            species: species.id,
-                            levels: p.min_level..=p.max_level,
-                        })
-                        .or_else(|| {
-                            eprintln!(
-                                "Could not get wild pokemon species {} because it does not exist!",
-                                p.species
+/// Groups of the fishing slots, in the order of their [`WildType::Fishing`] index.
+pub const FISHING_RODS: [&str; 3] = ["old_rod", "good_rod", "super_rod"];
+
+/// Every wild table of a map, ordered by their position in wild_encounters.json
+pub type Encounters = DashMap<String, Vec<((usize, usize), MapEncounters)>, RandomState>;
+
+#[derive(Default)]
+pub struct MapEncounters {
+    pub entries: HashMap<WildType, WildEntry>,
+    /// Encounter weight of each of an entry's encounters, in the same order
+    pub slots: HashMap<WildType, Vec<u8>>,
+}
+
+impl MapEncounters {
+    fn insert(
+        &mut self,
+        wild: WildType,
+        entry: JsonWildEncounterType,
+        rates: &[u8],
+        pokedex: &BasicDex<Pokemon, Arc<Pokemon>>,
+    ) {
+        let (entry, slots) = entry.into(rates, pokedex);
+        if !rates.is_empty() {
+            self.slots.insert(wild, slots);
+        }
+        self.entries.insert(wild, entry);
+    }
+}
+
+impl JsonWildEncounter {
+    pub fn into(
+        self,
+        fields: &[JsonWildType],
+        pokedex: &BasicDex<Pokemon, Arc<Pokemon>>,
+    ) -> MapEncounters {
+        let mut encounters = MapEncounters::default();
+        for (kind, wild, entry) in [
+            ("land_mons", WildType::Land, self.land_mons),
+            ("water_mons", WildType::Water, self.water_mons),
+            ("rock_smash_mons", WildType::Rock, self.rock_smash_mons),
+        ] {
+            if let Some(entry) = entry {
+                let rates = fields
+                    .iter()
+                    .find(|field| field.kind == kind)
+                    .map(|field| field.encounter_rates.as_slice())
+                    .unwrap_or_default();
+                encounters.insert(wild, entry, rates, pokedex);
+            }
+        }
+        if let Some(entry) = self.fishing_mons {
+            let field = fields.iter().find(|field| field.kind == "fishing_mons");
+            match field.filter(|field| !field.groups.is_empty()) {
+                // each rod uses its own group of the fishing slots
+                Some(field) => {
+                    for (group, rod) in FISHING_RODS.iter().enumerate() {
+                        if let Some(slots) = field.groups.get(*rod) {
+                            let rod = JsonWildEncounterType {
+                                encounter_rate: entry.encounter_rate,
+                                mons: slots
+                                    .iter()
+                                    .flat_map(|slot| entry.mons.get(*slot as usize))
+                                    .cloned()
+                                    .collect(),
+                            };
+                            let rates = slots
+                                .iter()
+                                .flat_map(|slot| field.encounter_rates.get(*slot as usize))
+                                .copied()
+                                .collect::<Vec<_>>();
+                            encounters.insert(
+                                WildType::Fishing(group as _),
+                                rod,
+                                &rates,
+                                pokedex,
                             );
-                            None
-                        })
-                })
-                .collect(),
+                        }
+                    }
+                }
+                None => {
+                    let rates = field
+                        .map(|field| field.encounter_rates.as_slice())
+                        .unwrap_or_default();
+                    encounters.insert(WildType::Fishing(0), entry, rates, pokedex);
+                }
+            }
         }
+        encounters
+    }
+}
+
+impl JsonWildEncounterType {
+    /// Converts the table along with the weight of each slot in `rates`.
+    ///
+    /// Slots whose species cannot be found are left out of both,
+    /// so the weights stay aligned with the encounters.
+    pub fn into(
+        self,
+        rates: &[u8],
+        pokedex: &BasicDex<Pokemon, Arc<Pokemon>>,
+    ) -> (WildEntry, Vec<u8>) {
+        let (encounters, slots): (Vec<_>, Vec<_>) = self
+            .mons
+            .into_par_iter()
+            .enumerate()
+            .flat_map(|(slot, mut p)| {
+                let species = &mut p.species[8..];
+                unsafe {
+                    let find = '_' as u8;
+                    let replace = '-' as u8;
+                    species.as_bytes_mut().iter_mut().for_each(|u| {
+                        if *u == find {
+                            *u = replace;
+                        }
+                    })
+                }
+                pokedex.try_get_named(&species)
+                    .map(|species| {
+                        (
+                            WildPokemon {
+                                species: species.id,
+                                levels: p.min_level..=p.max_level,
+                            },
+                            rates.get(slot).copied().unwrap_or_default(),
+                        )
+                    })
+                    .or_else(|| {
+                        eprintln!(
+                            "Could not get wild pokemon species {} because it does not exist!",
+                            p.species
+                        );
+                        None
+                    })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .unzip();
+        (
+            WildEntry {
+                ratio: self.encounter_rate,
+                encounters: encounters.into_iter().collect(),
+            },
+            slots,
+        )
     }
 
 }
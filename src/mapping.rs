@@ -1,4 +1,8 @@
-use std::ops::Deref;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Deref,
+    path::Path,
+};
 
 use firecore_world_builder::{
     builder::structs::BuilderLocation,
@@ -6,66 +10,179 @@ use firecore_world_builder::{
         audio::{SoundId, SoundVariant},
         character::npc::group::NpcGroupId,
         map::{object::ObjectId, PaletteId, TileId, TransitionId},
+        pokedex::item::ItemId,
         positions::{Direction, Location},
     },
 };
 use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    behavior::Passability, ForcedMovement, ParsedData, TerrainSound, WarpTrigger, WaterTile,
+    Weather,
+};
+
+/// Mappings for pokefirered shipped with the crate.
+pub const BUILTIN_MAPPINGS: &str = include_str!("../mappings.ron");
+
+/// Every field is optional, so a mappings file only needs to contain
+/// the entries it overrides when layered with [`NameMappings::load`].
 #[derive(Default, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
+#[serde(default, deny_unknown_fields)]
 pub struct NameMappings {
     pub map: MapMappings,
     pub palettes: PaletteMappings,
     pub music: HashMap<String, tinystr::TinyStr16>,
     pub npcs: NpcMappings,
     pub objects: ObjectMappings,
+    /// Item ids of pret item constants whose [derived id](crate::convert::into_item_id) is wrong
+    pub items: HashMap<String, ItemId>,
+    /// Dex names of pret species constants whose [derived name](crate::convert::species_name) is wrong
+    pub species: HashMap<String, String>,
+    /// Dex names of pret move constants whose [derived name](crate::convert::move_name) is wrong
+    pub moves: HashMap<String, String>,
     pub audio: AudioMappings,
+    pub wild: WildMappings,
+    pub trainers: TrainerMappings,
+    pub progression: ProgressionMappings,
+    pub behaviors: BehaviorMappings,
 }
 
 #[derive(Default, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
+#[serde(default, deny_unknown_fields)]
 pub struct MapMappings {
     pub id: IdMappings,
     pub name: HashMap<String, String>,
     pub transition: HashMap<String, TransitionId>,
+    /// Music constants played in wild battles of a map instead of the usual theme, keyed by pret map id
+    pub encounter_music: HashMap<String, String>,
+    /// Weather of maps with a pret weather constant, such as `WEATHER_RAIN`
+    pub weather: HashMap<String, Weather>,
+    /// Engine battle backgrounds keyed by pret battle scene, such as `MAP_BATTLE_SCENE_GYM`
+    pub battle_backgrounds: HashMap<String, String>,
+    /// Engine region map sections keyed by pret `MAPSEC_*` constant
+    pub sections: HashMap<String, String>,
+    /// Where warps to a missing warp id of a map land, keyed by pret map id
+    pub warp_fallbacks: HashMap<String, (i32, i32)>,
 }
 
 #[derive(Default, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
+#[serde(default, deny_unknown_fields)]
 pub struct PaletteMappings {
     pub primary: HashMap<String, PaletteId>,
     pub secondary: HashMap<String, PaletteId>,
     pub sizes: HashMap<PaletteId, TileId>,
+    /// Engine palettes keyed by the [hash](crate::tileset::hash) of the tileset they were made from
+    pub hashes: HashMap<u32, PaletteId>,
 }
 
 #[derive(Default, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
+#[serde(default, deny_unknown_fields)]
 pub struct NpcMappings {
     pub groups: HashMap<String, NpcGroupId>,
     pub movement: HashMap<String, (bool, HashSet<Direction>)>,
+    /// Idle animation played by NPCs with a pret movement type, such as jogging in place
+    pub animations: HashMap<String, String>,
 }
 
 #[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct ObjectMappings {
     pub objects: HashMap<String, ObjectId>,
+    /// Large object events that are only scenery, such as the S.S. Anne
+    pub decorations: HashMap<String, DecorationMapping>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DecorationMapping {
+    pub sprite: String,
+    /// Size of the sprite in tiles
+    pub width: u8,
+    pub height: u8,
 }
 
 #[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct AudioMappings {
     pub sounds: HashMap<String, (SoundId, SoundVariant)>,
 }
 
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WildMappings {
+    /// Variable that selects between a map's wild tables, keyed by map id
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrainerMappings {
+    /// Engine AI difficulty for each pret AI script flag.
+    /// A trainer uses the highest difficulty among its flags.
+    #[serde(default)]
+    pub ai: HashMap<String, u8>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProgressionMappings {
+    /// Prefixes of pret map ids that can only be reached after the Elite Four
+    pub postgame_maps: Vec<String>,
+    /// Flags set once the Elite Four is beaten, such as `FLAG_SYS_GAME_CLEAR`
+    pub postgame_flags: HashSet<String>,
+}
+
+impl ProgressionMappings {
+    pub fn is_postgame(&self, map: &str) -> bool {
+        self.postgame_maps
+            .iter()
+            .any(|prefix| map.starts_with(prefix.as_str()))
+    }
+}
+
+/// Sets of pret metatile behaviors, such as `MB_POND_WATER`, read from each map's tiles.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BehaviorMappings {
+    /// Behaviors of tiles that reflect the player and NPCs standing next to them
+    pub reflective: HashSet<String>,
+    /// Sounds of tiles walked on, keyed by behavior
+    pub terrain: HashMap<String, TerrainSound>,
+    /// How warps on tiles with a behavior are taken, such as through `MB_WARP_DOOR`
+    pub warps: HashMap<String, WarpTrigger>,
+    /// Behaviors that decide whether their tiles can be crossed, instead of the tiles' collision
+    pub passability: HashMap<String, Passability>,
+    /// Direction ledges with a behavior are jumped down in, such as `MB_JUMP_SOUTH`
+    pub ledges: HashMap<String, Direction>,
+    /// Behaviors of tiles that can start land encounters, such as tall grass and cave floors
+    pub encounters: HashSet<String>,
+    /// Water that needs Waterfall or Dive, such as `MB_WATERFALL`
+    pub water: HashMap<String, WaterTile>,
+    /// Tiles that move the player on their own, such as `MB_ICE` and `MB_SPIN_RIGHT`
+    pub forced: HashMap<String, ForcedMovement>,
+    /// Behaviors of counters that NPCs can be talked to across, such as `MB_COUNTER`
+    pub counters: HashSet<String>,
+}
+
 #[derive(Default, Deserialize, Serialize)]
 #[serde(transparent, deny_unknown_fields)]
 pub struct IdMappingsFrom {
     pub inner: HashMap<String, BuilderLocation>,
 }
 
-#[derive(Default, Deserialize, Serialize)]
+#[derive(Default, Deserialize)]
 #[serde(from = "IdMappingsFrom")]
 pub struct IdMappings {
     inner: HashMap<String, Location>,
+    /// Locations as they were written, so the mappings serialize back into the same form
+    builder: HashMap<String, BuilderLocation>,
+}
+
+impl Serialize for IdMappings {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.builder.serialize(serializer)
+    }
 }
 
 impl Deref for IdMappings {
@@ -81,9 +198,502 @@ impl From<IdMappingsFrom> for IdMappings {
         Self {
             inner: mappings
                 .inner
-                .into_iter()
-                .map(|(k, v)| (k, v.into()))
+                .iter()
+                .map(|(k, v)| (k.clone(), (*v).into()))
                 .collect(),
+            builder: mappings.inner,
+        }
+    }
+}
+
+impl NameMappings {
+    pub fn builtin() -> Self {
+        ron::from_str(BUILTIN_MAPPINGS).expect("Could not deserialize built-in mappings")
+    }
+
+    /// Reads a TOML file if it has a `.toml` extension, or a RON file otherwise.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        Ok(match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&data)?,
+            _ => ron::from_str(&data)?,
+        })
+    }
+
+    /// Writes TOML for a path with a `.toml` extension, or RON otherwise.
+    pub fn to_file(&self, path: &Path) -> anyhow::Result<String> {
+        Ok(match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::to_string_pretty(self)?,
+            _ => ron::ser::to_string_pretty(self, Default::default())?,
+        })
+    }
+
+    /// Layers mapping files over the [built-in](Self::builtin) mappings.
+    /// Later files override earlier ones and files that do not exist are skipped.
+    pub fn load<P: AsRef<Path>>(layers: &[P]) -> anyhow::Result<Self> {
+        let mut mappings = Self::builtin();
+        for path in layers.iter().map(AsRef::as_ref).filter(|p| p.exists()) {
+            mappings.merge(
+                Self::from_file(path)
+                    .map_err(|err| anyhow::anyhow!("Could not load {:?}: {}", path, err))?,
+            );
+        }
+        Ok(mappings)
+    }
+
+    /// Overrides entries with the ones in `other`.
+    pub fn merge(&mut self, other: Self) {
+        self.map.id.inner.extend(other.map.id.inner);
+        self.map.id.builder.extend(other.map.id.builder);
+        self.map.name.extend(other.map.name);
+        self.map.transition.extend(other.map.transition);
+        self.map.encounter_music.extend(other.map.encounter_music);
+        self.map.weather.extend(other.map.weather);
+        self.map
+            .battle_backgrounds
+            .extend(other.map.battle_backgrounds);
+        self.map.sections.extend(other.map.sections);
+        self.map.warp_fallbacks.extend(other.map.warp_fallbacks);
+        self.palettes.primary.extend(other.palettes.primary);
+        self.palettes.secondary.extend(other.palettes.secondary);
+        self.palettes.sizes.extend(other.palettes.sizes);
+        self.palettes.hashes.extend(other.palettes.hashes);
+        self.music.extend(other.music);
+        self.npcs.groups.extend(other.npcs.groups);
+        self.npcs.movement.extend(other.npcs.movement);
+        self.npcs.animations.extend(other.npcs.animations);
+        self.objects.objects.extend(other.objects.objects);
+        self.objects.decorations.extend(other.objects.decorations);
+        self.items.extend(other.items);
+        self.species.extend(other.species);
+        self.moves.extend(other.moves);
+        self.audio.sounds.extend(other.audio.sounds);
+        self.wild.variables.extend(other.wild.variables);
+        self.trainers.ai.extend(other.trainers.ai);
+        self.progression
+            .postgame_maps
+            .extend(other.progression.postgame_maps);
+        self.progression
+            .postgame_flags
+            .extend(other.progression.postgame_flags);
+        self.behaviors.reflective.extend(other.behaviors.reflective);
+        self.behaviors.terrain.extend(other.behaviors.terrain);
+        self.behaviors.warps.extend(other.behaviors.warps);
+        self.behaviors
+            .passability
+            .extend(other.behaviors.passability);
+        self.behaviors.ledges.extend(other.behaviors.ledges);
+        self.behaviors.encounters.extend(other.behaviors.encounters);
+        self.behaviors.water.extend(other.behaviors.water);
+        self.behaviors.forced.extend(other.behaviors.forced);
+        self.behaviors.counters.extend(other.behaviors.counters);
+    }
+}
+
+/// Path of pret's table of object event graphics, used by [`NameMappings::infer_npc_groups`].
+pub const GRAPHICS_INFO_POINTERS: &str =
+    "src/data/object_events/object_event_graphics_info_pointers.h";
+
+/// Path of pret's song constants, used by [`NameMappings::infer_music`].
+pub const SONGS_HEADER: &str = "include/constants/songs.h";
+
+/// Prefix of pret's music constants.
+pub const MUSIC_PREFIX: &str = "MUS_";
+
+/// Derives a music id from a pret constant, such as `pallet` from `MUS_PALLET`.
+pub fn music_name(constant: &str) -> Option<tinystr::TinyStr16> {
+    constant
+        .strip_prefix(MUSIC_PREFIX)
+        .and_then(|name| name.to_ascii_lowercase().parse().ok())
+}
+
+/// Result of [`NameMappings::infer_npc_groups`].
+#[derive(Debug, Default)]
+pub struct InferredNpcGroups {
+    pub groups: HashMap<String, NpcGroupId>,
+    /// Graphics ids whose group could not be inferred
+    pub unknown: BTreeSet<String>,
+}
+
+impl InferredNpcGroups {
+    /// Writes a mappings file with the inferred groups, and an empty group for every unknown
+    /// graphics id, to be reviewed and filled in by hand.
+    pub fn skeleton(&self) -> String {
+        let mut skeleton = String::from("(\n    npcs: (\n        groups: {\n");
+        let groups = self.groups.iter().collect::<BTreeMap<_, _>>();
+        for (id, group) in groups {
+            skeleton.push_str(&format!("            {:?}: {:?},\n", id, group.as_str()));
+        }
+        for id in self.unknown.iter() {
+            skeleton.push_str(&format!("            {:?}: \"\",\n", id));
         }
+        skeleton.push_str("        },\n    ),\n)\n");
+        skeleton
     }
-}
\ No newline at end of file
+}
+
+/// Names in the parsed data that have no mapping, found by [`NameMappings::check`].
+#[derive(Debug, Default, Serialize)]
+pub struct MissingMappings {
+    pub map_ids: BTreeSet<String>,
+    pub map_names: BTreeSet<String>,
+    pub primary_tilesets: BTreeSet<String>,
+    pub secondary_tilesets: BTreeSet<String>,
+    pub music: BTreeSet<String>,
+    /// Graphics ids with neither an NPC group nor an object mapping
+    pub graphics_ids: BTreeSet<String>,
+}
+
+impl NameMappings {
+    /// Cross-references every parsed map against the mappings.
+    pub fn check(&self, data: &ParsedData) -> MissingMappings {
+        let coverage = self.coverage(data);
+        MissingMappings {
+            map_ids: coverage.map_ids.unmapped.into_keys().collect(),
+            map_names: coverage.map_names.unmapped.into_keys().collect(),
+            primary_tilesets: coverage.primary_tilesets.unmapped.into_keys().collect(),
+            secondary_tilesets: coverage.secondary_tilesets.unmapped.into_keys().collect(),
+            music: coverage.music.unmapped.into_keys().collect(),
+            graphics_ids: coverage.graphics_ids.unmapped.into_keys().collect(),
+        }
+    }
+
+    /// Counts how many uses of each kind of name in the parsed maps the mappings resolve.
+    pub fn coverage(&self, data: &ParsedData) -> Coverage {
+        let mut coverage = Coverage::default();
+        for map in data.maps.iter() {
+            let map = map.value();
+            coverage
+                .map_ids
+                .add(&map.data.id, self.map.id.contains_key(&map.data.id));
+            coverage
+                .map_names
+                .add(&map.data.name, self.map.name.contains_key(&map.data.name));
+            coverage.primary_tilesets.add(
+                &map.layout.primary_tileset,
+                self.palettes
+                    .primary
+                    .contains_key(&map.layout.primary_tileset),
+            );
+            coverage.secondary_tilesets.add(
+                &map.layout.secondary_tileset,
+                self.palettes
+                    .secondary
+                    .contains_key(&map.layout.secondary_tileset),
+            );
+            coverage
+                .music
+                .add(&map.data.music, self.music.contains_key(&map.data.music));
+            for event in map.data.object_events.iter() {
+                coverage.graphics_ids.add(
+                    &event.graphics_id,
+                    self.npcs.groups.contains_key(&event.graphics_id)
+                        || self.objects.objects.contains_key(&event.graphics_id)
+                        || self.objects.decorations.contains_key(&event.graphics_id)
+                        || event.graphics_id == crate::convert::ITEM_BALL_GRAPHICS,
+                );
+                if self.npcs.groups.contains_key(&event.graphics_id) {
+                    coverage.movement_types.add(
+                        &event.movement_type,
+                        self.npcs.movement.contains_key(&event.movement_type),
+                    );
+                }
+            }
+        }
+        coverage
+    }
+}
+
+/// How many uses of one kind of name were resolved by the mappings.
+#[derive(Debug, Default, Serialize)]
+pub struct CategoryCoverage {
+    pub resolved: usize,
+    /// Number of uses of each unmapped name
+    pub unmapped: BTreeMap<String, usize>,
+}
+
+impl CategoryCoverage {
+    fn add(&mut self, name: &str, mapped: bool) {
+        match mapped {
+            true => self.resolved += 1,
+            false => *self.unmapped.entry(name.to_owned()).or_default() += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.resolved + self.unmapped.values().sum::<usize>()
+    }
+
+    pub fn percent(&self) -> f32 {
+        match self.total() {
+            0 => 100.0,
+            total => self.resolved as f32 * 100.0 / total as f32,
+        }
+    }
+
+    /// The unmapped names used the most, most used first.
+    pub fn offenders(&self, count: usize) -> Vec<(&str, usize)> {
+        let mut offenders = self
+            .unmapped
+            .iter()
+            .map(|(name, uses)| (name.as_str(), *uses))
+            .collect::<Vec<_>>();
+        offenders.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        offenders.truncate(count);
+        offenders
+    }
+}
+
+/// Result of [`NameMappings::coverage`].
+#[derive(Debug, Default, Serialize)]
+pub struct Coverage {
+    pub map_ids: CategoryCoverage,
+    pub map_names: CategoryCoverage,
+    pub primary_tilesets: CategoryCoverage,
+    pub secondary_tilesets: CategoryCoverage,
+    pub music: CategoryCoverage,
+    pub graphics_ids: CategoryCoverage,
+    /// Movement types of object events with an NPC group mapping
+    pub movement_types: CategoryCoverage,
+}
+
+impl Coverage {
+    pub fn categories(&self) -> [(&'static str, &CategoryCoverage); 7] {
+        [
+            ("map ids", &self.map_ids),
+            ("map names", &self.map_names),
+            ("primary tilesets", &self.primary_tilesets),
+            ("secondary tilesets", &self.secondary_tilesets),
+            ("music", &self.music),
+            ("graphics ids", &self.graphics_ids),
+            ("movement types", &self.movement_types),
+        ]
+    }
+}
+
+impl std::fmt::Display for Coverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (category, coverage) in self.categories() {
+            writeln!(
+                f,
+                "{}: {:.1}% of {} resolved",
+                category,
+                coverage.percent(),
+                coverage.total()
+            )?;
+            for (name, uses) in coverage.offenders(5) {
+                writeln!(f, "    {} ({} uses)", name, uses)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NameMappings {
+    /// Infers NPC groups from pret's [graphics table](GRAPHICS_INFO_POINTERS)
+    /// for graphics ids without an NPC group or object mapping.
+    ///
+    /// Groups are named like the built-in mappings, as the graphics id in lowercase
+    /// without its `OBJ_EVENT_GFX_` prefix.
+    pub fn infer_npc_groups(&self, pointers: &str) -> InferredNpcGroups {
+        const PREFIX: &str = "OBJ_EVENT_GFX_";
+
+        let mut inferred = InferredNpcGroups::default();
+
+        for line in pointers.lines().map(str::trim) {
+            let id = match line
+                .strip_prefix('[')
+                .and_then(|line| line.split_once(']'))
+                .filter(|(.., pointer)| pointer.trim_start().starts_with('='))
+            {
+                Some((id, ..)) => id.trim(),
+                None => continue,
+            };
+
+            if self.npcs.groups.contains_key(id)
+                || self.objects.objects.contains_key(id)
+                || self.objects.decorations.contains_key(id)
+            {
+                continue;
+            }
+
+            match id
+                .strip_prefix(PREFIX)
+                .and_then(|name| name.to_ascii_lowercase().parse::<NpcGroupId>().ok())
+            {
+                Some(group) => {
+                    inferred.groups.insert(id.to_owned(), group);
+                }
+                None => {
+                    inferred.unknown.insert(id.to_owned());
+                }
+            }
+        }
+
+        inferred
+    }
+}
+
+impl NameMappings {
+    /// Derives a [music name](music_name) for every constant in pret's
+    /// [song constants](SONGS_HEADER) that has no music mapping.
+    pub fn infer_music(&self, songs: &str) -> BTreeMap<String, tinystr::TinyStr16> {
+        songs
+            .lines()
+            .flat_map(|line| {
+                let mut words = line.split_whitespace();
+                match words.next() {
+                    Some("#define") => words.next(),
+                    _ => None,
+                }
+            })
+            .filter(|constant| !self.music.contains_key(*constant))
+            .flat_map(|constant| Some((constant.to_owned(), music_name(constant)?)))
+            .collect()
+    }
+}
+
+/// Writes inferred music as a mappings file to review before adding it to the project mappings.
+pub fn music_file(music: &BTreeMap<String, tinystr::TinyStr16>) -> String {
+    let mut file = String::from("(\n    music: {\n");
+    for (constant, name) in music.iter() {
+        file.push_str(&format!("        {:?}: {:?},\n", constant, name.as_str()));
+    }
+    file.push_str("    },\n)\n");
+    file
+}
+
+/// Largest edit distance between normalized names accepted by [`NameMappings::fuzzy_map_name`].
+pub const FUZZY_DISTANCE: usize = 2;
+
+impl NameMappings {
+    /// Finds the map name mapping closest to a pret map name that has no exact mapping,
+    /// comparing names without case or punctuation, returning the matched key and its name.
+    ///
+    /// Keys that are `parsed` maps of their own and names whose numbers differ,
+    /// such as `SSAnne_1F` and `SSAnne_2F`, never match.
+    pub fn fuzzy_map_name(
+        &self,
+        name: &str,
+        parsed: impl Fn(&str) -> bool,
+    ) -> Option<(&str, &str)> {
+        fn normalize(name: &str) -> Vec<char> {
+            name.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        }
+
+        fn digits(name: &[char]) -> Vec<char> {
+            name.iter().copied().filter(char::is_ascii_digit).collect()
+        }
+
+        let name = normalize(name);
+
+        self.map
+            .name
+            .iter()
+            .filter(|(key, ..)| !parsed(key))
+            .flat_map(|(key, value)| {
+                let normalized = normalize(key);
+                (digits(&normalized) == digits(&name))
+                    .then(|| (distance(&name, &normalized), key, value))
+            })
+            .filter(|(distance, ..)| *distance <= FUZZY_DISTANCE)
+            .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+            .map(|(.., key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+/// Levenshtein distance between two strings.
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.iter().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous + (a != b) as usize;
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+impl MissingMappings {
+    pub fn categories(&self) -> [(&'static str, &BTreeSet<String>); 6] {
+        [
+            ("map ids", &self.map_ids),
+            ("map names", &self.map_names),
+            ("primary tilesets", &self.primary_tilesets),
+            ("secondary tilesets", &self.secondary_tilesets),
+            ("music", &self.music),
+            ("graphics ids", &self.graphics_ids),
+        ]
+    }
+
+    pub fn len(&self) -> usize {
+        self.categories()
+            .iter()
+            .map(|(.., names)| names.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Display for MissingMappings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (category, names) in self.categories() {
+            if names.is_empty() {
+                continue;
+            }
+            writeln!(f, "Missing {} ({}):", category, names.len())?;
+            for name in names {
+                writeln!(f, "    {}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_edits_between_names() {
+        let chars = |name: &str| name.chars().collect::<Vec<_>>();
+        assert_eq!(distance(&chars("route2"), &chars("route2")), 0);
+        assert_eq!(distance(&chars("ssanne1f"), &chars("ssanne2f")), 1);
+        assert_eq!(distance(&chars("mtmoon"), &chars("mtmoonb1f")), 3);
+        assert_eq!(distance(&chars(""), &chars("cave")), 4);
+    }
+
+    #[test]
+    fn fuzzy_names_keep_their_numbers() {
+        let mut mappings = NameMappings::default();
+        for (key, name) in [
+            ("SSAnne_2F_Corridor", "S.S. Anne 2F"),
+            ("ViridianCity_Mart", "Viridian Mart"),
+            ("Route3", "Route 3"),
+        ] {
+            mappings.map.name.insert(key.to_owned(), name.to_owned());
+        }
+        let parsed = |key: &str| key == "Route3";
+
+        assert_eq!(
+            mappings.fuzzy_map_name("ViridianCity_PokeMart", parsed),
+            None
+        );
+        assert_eq!(
+            mappings.fuzzy_map_name("Viridian_City_Mart", parsed),
+            Some(("ViridianCity_Mart", "Viridian Mart"))
+        );
+        assert_eq!(mappings.fuzzy_map_name("SSAnne_1F_Corridor", parsed), None);
+        assert_eq!(mappings.fuzzy_map_name("Route4", parsed), None);
+        assert_eq!(mappings.fuzzy_map_name("Route_3", parsed), None);
+    }
+}
@@ -0,0 +1,496 @@
+use firecore_world_builder::world::{
+    character::npc::NpcId,
+    map::movement::Elevation,
+    map::warp::WarpDestination,
+    map::wild::{WildEntry, WildPokemon, WildType},
+    pokedex::{
+        item::ItemId,
+        pokemon::{owned::SavedPokemon, PokemonId},
+    },
+    positions::{BoundingBox, Coordinate, Direction, Location},
+};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use tinystr::TinyStr16;
+
+/// Data converted from pret that has no place in a [`WorldMap`](firecore_world_builder::world::map::WorldMap).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MapMetadata {
+    /// Whether the map can only be reached after the Elite Four
+    #[serde(default)]
+    pub postgame: bool,
+    /// Position of the map in pret's map groups
+    #[serde(default)]
+    pub group: Option<MapGroupIndex>,
+    /// Extra data for NPCs, keyed by their id in the map
+    #[serde(default)]
+    pub npcs: HashMap<NpcId, NpcMetadata>,
+    /// pret's encounter weight of each wild encounter, in the order of the map's wild entries
+    #[serde(default)]
+    pub wild_slots: HashMap<WildType, Vec<u8>>,
+    /// Extra wild tables switched between at runtime, such as Altering Cave's
+    #[serde(default)]
+    pub wild_tables: Option<WildTables>,
+    /// Outbreak species added through [`MapEdits`](crate::MapEdits)
+    #[serde(default)]
+    pub outbreaks: Vec<Outbreak>,
+    /// Battles started by the map's scripts instead of by trainers
+    #[serde(default)]
+    pub scripted_battles: Vec<ScriptedBattle>,
+    /// Pokemon battled by interacting with them on the map, such as Snorlax
+    #[serde(default)]
+    pub static_encounters: Vec<StaticEncounter>,
+    /// Scripts that run when the player steps on a tile, from pret's coord events
+    #[serde(default)]
+    pub triggers: Vec<CoordTrigger>,
+    /// Interactions that only work while the player faces certain directions
+    #[serde(default)]
+    pub facing: Vec<FacingRequirement>,
+    /// Names of the scripts from the map's `scripts.inc`, lowered into
+    /// [`WorldScriptData`](firecore_world_builder::world::script::WorldScriptData)
+    /// or kept in [`WorldData::raw_scripts`](crate::WorldData::raw_scripts)
+    #[serde(default)]
+    pub scripts: Vec<String>,
+    /// Scenery converted from object events, such as the S.S. Anne at Vermilion's harbor
+    #[serde(default)]
+    pub decorations: Vec<Decoration>,
+    /// Music of wild battles on the map, if it is not the usual wild battle theme
+    #[serde(default)]
+    pub encounter_music: Option<TinyStr16>,
+    /// Flags of the map's hidden items and item balls, set once they are picked up
+    #[serde(default)]
+    pub item_flags: Vec<ItemFlag>,
+    /// Tiles that show reflections, from the [reflective](crate::BehaviorMappings::reflective) behaviors
+    #[serde(default)]
+    pub reflections: Vec<Coordinate>,
+    /// Weather shown on the map, from its [weather mapping](crate::MapMappings::weather)
+    #[serde(default)]
+    pub weather: Option<Weather>,
+    /// Background of battles on the map, from its pret battle scene
+    #[serde(default)]
+    pub battle_background: Option<String>,
+    /// Kind of area the map is, which decides where Teleport and Fly work
+    #[serde(default)]
+    pub map_type: Option<MapType>,
+    /// Whether the player can ride their bike on the map
+    #[serde(default)]
+    pub allow_cycling: bool,
+    /// Whether the player can run on the map
+    #[serde(default)]
+    pub allow_running: bool,
+    /// Whether Escape Rope and Dig work on the map
+    #[serde(default)]
+    pub allow_escaping: bool,
+    /// Whether the map's name pops up when the player enters it
+    #[serde(default)]
+    pub show_map_name: bool,
+    /// Whether the map is dark until the player uses Flash, such as Rock Tunnel
+    #[serde(default)]
+    pub requires_flash: bool,
+    /// Section of the region map the map is in, for the Town Map and location names
+    #[serde(default)]
+    pub region_map_section: Option<String>,
+    /// Maps reached by diving and emerging, which only Emerald's sources have
+    #[serde(default)]
+    pub vertical: VerticalConnections,
+    /// Warps whose destination the engine has to decide, such as exits back outside
+    #[serde(default)]
+    pub dynamic_warps: Vec<DynamicWarp>,
+    /// How each of the map's warps is taken, from the behaviors of their tiles
+    #[serde(default)]
+    pub warps: Vec<WarpMetadata>,
+    /// Tiles that make a sound when walked on, from the [terrain](crate::BehaviorMappings::terrain) behaviors
+    #[serde(default)]
+    pub terrain: HashMap<TerrainSound, Vec<Coordinate>>,
+    /// Ledges keyed by the direction they are jumped down in, from the [ledge](crate::BehaviorMappings::ledges) behaviors.
+    /// Their movements block walking onto them, so they are only crossed by jumping.
+    #[serde(default)]
+    pub ledges: HashMap<Direction, Vec<Coordinate>>,
+    /// Tiles that can start land encounters, from the [encounter](crate::BehaviorMappings::encounters) behaviors.
+    /// Water encounters can start on any tile that is surfed on.
+    #[serde(default)]
+    pub encounter_tiles: Vec<Coordinate>,
+    /// Water tiles that need Waterfall or Dive, from the [water](crate::BehaviorMappings::water) behaviors.
+    /// Their movements are surfable, like the rest of the map's water.
+    #[serde(default)]
+    pub water: HashMap<WaterTile, Vec<Coordinate>>,
+    /// Tiles that move the player on their own, from the [forced movement](crate::BehaviorMappings::forced) behaviors
+    #[serde(default)]
+    pub forced: HashMap<ForcedMovement, Vec<Coordinate>>,
+    /// Counters the player can talk to NPCs across, such as in marts and Pokemon Centers,
+    /// from the [counter](crate::BehaviorMappings::counters) behaviors
+    #[serde(default)]
+    pub counters: Vec<Coordinate>,
+    /// Elevation of each tile in row order, from the top bits of its [block](crate::convert::into_elevations)
+    #[serde(default)]
+    pub elevations: Vec<Elevation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapGroupIndex {
+    /// pret group name, such as `gMapGroup_TownsAndRoutes`
+    pub name: String,
+    /// Index of the group in `group_order`
+    pub group: usize,
+    /// Index of the map in its group
+    pub index: usize,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct NpcMetadata {
+    #[serde(default)]
+    pub trainer: Option<TrainerMetadata>,
+    /// Whether the NPC's script depends on beating the Elite Four
+    #[serde(default)]
+    pub postgame: bool,
+    /// When the NPC appears, from the schedules in [`Edits`](crate::Edits)
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    /// Idle animation the NPC plays instead of standing still
+    #[serde(default)]
+    pub animation: Option<String>,
+    /// pret flag that hides the NPC while it is set
+    #[serde(default)]
+    pub flag: Option<String>,
+    /// pret label of the script the NPC's object event runs, which schedules are keyed by
+    #[serde(default)]
+    pub script: Option<String>,
+    /// Area a wandering NPC stays within, from pret's `movement_range_x` and `movement_range_y`
+    #[serde(default)]
+    pub wander_area: Option<BoundingBox>,
+    /// Pokemon the NPC's script gives to the player, such as the starters or Lapras
+    #[serde(default)]
+    pub gifts: Vec<GiftPokemon>,
+    /// Trade the NPC's script offers, from pret's in-game trade table
+    #[serde(default)]
+    pub trade: Option<NpcTrade>,
+}
+
+impl NpcMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.trainer.is_none()
+            && !self.postgame
+            && self.schedule.is_none()
+            && self.animation.is_none()
+            && self.wander_area.is_none()
+            && self.flag.is_none()
+            && self.script.is_none()
+            && self.gifts.is_empty()
+            && self.trade.is_none()
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TrainerMetadata {
+    /// pret trainer id, such as `TRAINER_TWINS_ELI_AND_ANNE`
+    pub id: String,
+    /// Whether the trainer fights a double battle
+    pub double_battle: bool,
+    /// Other NPCs on the map that fight in the same double battle
+    pub partners: Vec<NpcId>,
+    /// Items the trainer can use in battle
+    pub items: Vec<ItemId>,
+    /// pret AI script flags, such as `AI_SCRIPT_CHECK_BAD_MOVE`
+    pub ai_flags: Vec<String>,
+    /// Engine AI difficulty derived from the AI flags
+    pub difficulty: Option<u8>,
+    /// Directions the trainer spots the player in, up to its sight range, before walking up to battle
+    #[serde(default)]
+    pub sight_directions: Vec<Direction>,
+    /// Whether the trainer hides in the sand until it spots the player
+    #[serde(default)]
+    pub buried: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WildTables {
+    /// Variable that selects the table, if known
+    pub var: Option<String>,
+    /// `tables[i]` is used when the variable equals `i + 1`; 0 keeps the map's own wild data
+    pub tables: Vec<HashMap<WildType, WildEntry>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Outbreak {
+    /// Table the outbreak appears in
+    pub wild: WildType,
+    /// Chance out of 100 that an encounter in the table is the outbreak species
+    pub chance: u8,
+    pub pokemon: WildPokemon,
+}
+
+/// Real-time clock conditions for an event, which is active when any of them match.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    #[serde(default)]
+    pub dates: Vec<Date>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Date {
+    /// Month of the year, from 1 to 12
+    pub month: u8,
+    pub day: u8,
+}
+
+/// Tiles that run a script when stepped on while a variable has a value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoordTrigger {
+    pub script: String,
+    /// Variable checked before running the script, such as `VAR_MAP_SCENE_PALLET_TOWN_OAK`
+    pub var: String,
+    /// Value the variable needs to have, as written in pret
+    pub value: String,
+    pub elevation: u8,
+    /// Tiles of the trigger, in rows from the top left
+    pub coords: Vec<Coordinate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FacingRequirement {
+    /// Position of the sign or object being interacted with
+    pub coords: Coordinate,
+    /// Directions the player can face, such as only [`Direction::Up`] for signs read from below
+    pub directions: Vec<Direction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemFlag {
+    /// Position of the item
+    pub coords: Coordinate,
+    /// pret flag, such as `FLAG_HIDDEN_ITEM_ROUTE_9_ETHER`
+    pub flag: String,
+}
+
+/// Warp that leads wherever the game last set its dynamic warp, from pret's
+/// `MAP_DYNAMIC` and `WARP_ID_DYNAMIC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DynamicWarp {
+    pub coords: Coordinate,
+    /// Map the warp leads to if pret names one, leaving only the position to decide
+    pub map: Option<Location>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerticalConnections {
+    /// Map below the water's surface
+    pub dive: Option<Location>,
+    /// Map above, reached by surfacing
+    pub emerge: Option<Location>,
+}
+
+/// Kind of area a map is, from pret's `MAP_TYPE_*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MapType {
+    Town,
+    City,
+    Route,
+    Underground,
+    Underwater,
+    OceanRoute,
+    Indoor,
+    SecretBase,
+}
+
+impl MapType {
+    /// Teleport and Fly only work in maps that are outside.
+    pub fn is_outdoors(&self) -> bool {
+        matches!(
+            self,
+            MapType::Town | MapType::City | MapType::Route | MapType::OceanRoute
+        )
+    }
+}
+
+/// Weather effect drawn over a map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Weather {
+    Sunny,
+    /// Sunny with clouds drifting by
+    Clouds,
+    Rain,
+    Thunderstorm,
+    Downpour,
+    Snow,
+    Sandstorm,
+    Fog,
+    /// Volcanic ash falling from the sky
+    Ash,
+    /// Harsh sunlight that brightens the screen
+    Drought,
+    Underwater,
+}
+
+/// How a warp is taken, from the [warp](crate::BehaviorMappings::warps) behavior of its tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WarpTrigger {
+    /// Walking into a door, which opens before the player goes through
+    Door,
+    /// Stepping onto the tile, such as a ladder or a cave entrance
+    Tile,
+    /// Walking off the tile in a direction, such as down through a building's exit mat
+    Arrow(Direction),
+    /// Walking onto stairs in a direction, which players arriving on them walk back out of
+    Stairs(Direction),
+    /// Stepping into a hole, which drops the player to the floor below with no way back up
+    Fall,
+}
+
+impl WarpTrigger {
+    /// Direction players face after arriving on a warp, such as out of a door.
+    pub fn arrival(&self) -> Option<Direction> {
+        match self {
+            WarpTrigger::Door => Some(Direction::Down),
+            WarpTrigger::Tile | WarpTrigger::Fall => None,
+            WarpTrigger::Arrow(direction) | WarpTrigger::Stairs(direction) => {
+                Some(match direction {
+                    Direction::Up => Direction::Down,
+                    Direction::Down => Direction::Up,
+                    Direction::Left => Direction::Right,
+                    Direction::Right => Direction::Left,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarpMetadata {
+    /// Id that stays the same when pret reorders warps, made from where the warp
+    /// leads and where it is, such as `pallet_5_8`
+    pub id: String,
+    /// Index of the warp in pret's `warp_events`, which scripts and other warps refer to it by
+    pub index: u8,
+    /// Position of the warp on its map
+    pub coords: Coordinate,
+    /// Whether stepping onto the tile warps, instead of walking off it in [`WarpMetadata::facing`]
+    pub warp_on_tile: bool,
+    /// Whether a player arriving on this warp takes a step off it, as when leaving through a door
+    pub move_on_exit: bool,
+    /// Direction the player has to walk in to take the warp
+    pub facing: Option<Direction>,
+    /// Direction a player arriving on this warp faces
+    pub arrival: Option<Direction>,
+    /// Whether the music restarts when taking the warp, which is not the case
+    /// between maps with the same music, such as a house and its town
+    pub change_music: bool,
+    /// Whether the warp is meant to have no warp back, such as a hole
+    pub one_way: bool,
+}
+
+/// Sound and particles played when stepping on a tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TerrainSound {
+    /// Rustling through tall or long grass
+    Grass,
+    Sand,
+    /// Splashing through shallow water or puddles
+    Water,
+    /// Echoing steps on cave floors
+    Cave,
+}
+
+/// Tile that moves the player without them pressing a direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ForcedMovement {
+    /// Ice, which slides players along the direction they stepped onto it in until they hit something
+    Slide,
+    /// Slides players in a direction, such as a water current or a sliding floor
+    Push(Direction),
+    /// Spins players along in a direction until they reach a [stop](ForcedMovement::Stop), as in the Rocket Hideout
+    Spin(Direction),
+    /// Ends a spin
+    Stop,
+}
+
+/// Water that needs more than Surf to cross.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WaterTile {
+    /// Climbed with Waterfall
+    Waterfall,
+    /// Deep water that can be dived into on maps with a map [below](VerticalConnections::dive)
+    Dive,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decoration {
+    pub sprite: String,
+    /// Top left tile of the sprite
+    pub coords: Coordinate,
+    pub width: u8,
+    pub height: u8,
+    /// Flag that hides the decoration when set, such as after the S.S. Anne leaves
+    pub flag: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptedBattle {
+    /// Script that starts the battle
+    pub script: String,
+    pub kind: ScriptedBattleKind,
+}
+
+/// Place the player can fly to, which is also where they respawn after whiting out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealLocation {
+    /// pret id, such as `SPAWN_PALLET_TOWN`
+    pub id: String,
+    /// Where the player lands when flying here
+    pub fly: WarpDestination,
+    /// Map the player respawns in, such as the town's Pokemon Center
+    pub respawn: Option<Location>,
+}
+
+/// Pokemon given by a script, from pret's `givemon` and `givecustommon`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GiftPokemon {
+    pub pokemon: SavedPokemon,
+    /// Script with the command that gives the pokemon
+    pub script: String,
+}
+
+/// Pokemon an NPC gives for one of the player's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NpcTrade {
+    /// pret trade id, such as `INGAME_TRADE_MR_MIME`
+    pub id: String,
+    /// Species the NPC wants from the player
+    pub requested: PokemonId,
+    /// Pokemon given in return, which takes the level of the requested one
+    pub pokemon: SavedPokemon,
+    pub nickname: String,
+    /// Name of the pokemon's original trainer
+    pub trainer: String,
+}
+
+/// Wild battle started by interacting with an object event, from pret's `setwildbattle`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticEncounter {
+    pub coords: Coordinate,
+    pub pokemon: SavedPokemon,
+    /// Flag that hides the pokemon once set, usually after the battle
+    pub flag: Option<String>,
+    /// Script that starts the battle
+    pub script: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScriptedBattleKind {
+    /// Battle where an NPC shows the player how to catch a pokemon
+    CatchTutorial,
+    /// First battle against the rival, which does not end the game when lost
+    EarlyRival { trainer: String },
+}
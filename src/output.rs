@@ -0,0 +1,551 @@
+use std::{collections::BTreeMap, path::Path};
+
+use firecore_world_builder::world::{
+    map::WorldMap,
+    positions::{CoordinateInt, Direction, Location},
+};
+use hashbrown::HashMap;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{NameMappings, ParsedData};
+
+/// Identifies files written by this tool.
+pub const MAGIC: [u8; 4] = *b"FWGN";
+
+/// Bumped whenever the layout of the output changes.
+pub const SCHEMA_VERSION: u16 = 1;
+
+/// Revision of firecore-world-builder this tool was built against.
+pub const WORLDLIB_VERSION: &str = "2c67ed2";
+
+/// Prepended to every serialized output file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    pub magic: [u8; 4],
+    pub schema: u16,
+    pub worldlib: String,
+}
+
+impl Header {
+    pub fn current() -> Self {
+        Self {
+            magic: MAGIC,
+            schema: SCHEMA_VERSION,
+            worldlib: WORLDLIB_VERSION.to_owned(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), HeaderError> {
+        if self.magic != MAGIC {
+            return Err(HeaderError::Magic);
+        }
+        if self.schema != SCHEMA_VERSION {
+            return Err(HeaderError::Schema(self.schema));
+        }
+        if self.worldlib != WORLDLIB_VERSION {
+            return Err(HeaderError::Worldlib(self.worldlib.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// Serializes a value behind the current [`Header`].
+pub fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, HeaderError> {
+    let mut bytes = postcard::to_allocvec(&Header::current())?;
+    bytes.extend(postcard::to_allocvec(value)?);
+    Ok(bytes)
+}
+
+/// Deserializes a value written by [`to_bytes`], refusing incompatible headers.
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, HeaderError> {
+    let (header, bytes) = postcard::take_from_bytes::<Header>(bytes)?;
+    header.validate()?;
+    Ok(postcard::from_bytes(bytes)?)
+}
+
+/// Checks the header of every `.bin` and `.world` file in a directory,
+/// returning how many files were checked.
+///
+/// Fails if there are no such files, as that usually means the wrong directory was given.
+pub fn validate(dir: &Path) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("bin" | "world") => {
+                validate_file(&path)?;
+                count += 1;
+            }
+            _ => (),
+        }
+    }
+    if count == 0 {
+        anyhow::bail!("{:?} has no .bin or .world files to validate", dir);
+    }
+    Ok(count)
+}
+
+/// Checks the header of a file written by [`to_bytes`].
+pub fn validate_file(path: &Path) -> anyhow::Result<()> {
+    let bytes = std::fs::read(path)?;
+    postcard::take_from_bytes::<Header>(&bytes)
+        .map_err(HeaderError::from)
+        .and_then(|(header, ..)| header.validate())
+        .map_err(|err| anyhow::anyhow!("{:?}: {}", path, err))
+}
+
+#[derive(Debug)]
+pub enum HeaderError {
+    Magic,
+    Schema(u16),
+    Worldlib(String),
+    Postcard(postcard::Error),
+}
+
+impl From<postcard::Error> for HeaderError {
+    fn from(err: postcard::Error) -> Self {
+        Self::Postcard(err)
+    }
+}
+
+impl std::error::Error for HeaderError {}
+
+impl std::fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderError::Magic => write!(f, "File was not written by world-gen"),
+            HeaderError::Schema(schema) => write!(
+                f,
+                "File has schema version {} but version {} is supported",
+                schema, SCHEMA_VERSION
+            ),
+            HeaderError::Worldlib(worldlib) => write!(
+                f,
+                "File was built against worldlib {} but this tool uses {}",
+                worldlib, WORLDLIB_VERSION
+            ),
+            HeaderError::Postcard(err) => write!(f, "Could not read file with error {}", err),
+        }
+    }
+}
+
+/// Display names of maps keyed by pret map name and then by language.
+pub type NameTable = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Combines the converted display names with translations keyed by language,
+/// which are keyed by pret map name like [`WorldData::names`](crate::WorldData::names).
+///
+/// Maps without a translation are left out of that language.
+pub fn name_table(
+    language: &str,
+    names: &BTreeMap<String, String>,
+    translations: &BTreeMap<String, HashMap<String, String>>,
+) -> NameTable {
+    names
+        .iter()
+        .map(|(map, name)| {
+            let mut languages = BTreeMap::new();
+            languages.insert(language.to_owned(), name.clone());
+            for (language, translation) in translations.iter() {
+                if let Some(name) = translation.get(map) {
+                    languages.insert(language.clone(), name.clone());
+                }
+            }
+            (map.clone(), languages)
+        })
+        .collect()
+}
+
+/// Lists every map written by [`write_split`], so engines can load maps lazily.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MapIndex {
+    pub maps: Vec<MapIndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapIndexEntry {
+    pub location: Location,
+    pub file: String,
+    pub width: CoordinateInt,
+    pub height: CoordinateInt,
+    pub connections: Vec<IndexConnection>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct IndexConnection {
+    pub direction: Direction,
+    pub location: Location,
+    pub offset: CoordinateInt,
+}
+
+impl MapIndex {
+    pub fn new(maps: &HashMap<Location, WorldMap>) -> Self {
+        let mut maps = maps
+            .par_iter()
+            .map(|(location, map)| MapIndexEntry {
+                location: *location,
+                file: file_name(location, "bin"),
+                width: map.width as _,
+                height: map.height as _,
+                connections: map
+                    .chunk
+                    .iter()
+                    .flat_map(|chunk| chunk.connections.iter())
+                    .flat_map(|(direction, connections)| {
+                        connections.iter().map(|connection| IndexConnection {
+                            direction: *direction,
+                            location: connection.0,
+                            offset: connection.1 as _,
+                        })
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        maps.sort_by(|a, b| a.file.cmp(&b.file));
+        Self { maps }
+    }
+}
+
+/// File name used for a map in the output directories.
+pub fn file_name(location: &Location, extension: &str) -> String {
+    match location.map {
+        Some(map) => format!("{}-{}.{}", map.as_str(), location.index.as_str(), extension),
+        None => format!("{}.{}", location.index.as_str(), extension),
+    }
+}
+
+/// pret's map groups, in `group_order`, for presenting maps the way pret organizes them.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct GroupIndex {
+    pub groups: Vec<GroupIndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupIndexEntry {
+    /// pret group name, such as `gMapGroup_TownsAndRoutes`
+    pub name: String,
+    pub maps: Vec<GroupIndexMap>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupIndexMap {
+    /// pret map name, such as `PalletTown`
+    pub name: String,
+    /// pret map id, such as `MAP_PALLET_TOWN`, if the map was parsed
+    pub id: Option<String>,
+    pub location: Option<Location>,
+}
+
+impl GroupIndex {
+    pub fn new(mappings: &NameMappings, data: &ParsedData) -> Self {
+        let ids = data
+            .maps
+            .iter()
+            .map(|map| (map.data.name.clone(), map.key().clone()))
+            .collect::<HashMap<_, _>>();
+        Self {
+            groups: data
+                .groups
+                .iter()
+                .map(|group| GroupIndexEntry {
+                    name: group.name.clone(),
+                    maps: group
+                        .maps
+                        .iter()
+                        .map(|name| {
+                            let id = ids.get(name).cloned();
+                            GroupIndexMap {
+                                name: name.clone(),
+                                location: id
+                                    .as_ref()
+                                    .map(|id| crate::convert::location(mappings, data, id)),
+                                id,
+                            }
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Writes each map to `<dir>/<location>.bin` along with an `index.bin` [`MapIndex`].
+pub fn write_split(dir: &Path, maps: &HashMap<Location, WorldMap>) -> anyhow::Result<MapIndex> {
+    std::fs::create_dir_all(dir)?;
+
+    maps.par_iter()
+        .try_for_each::<_, anyhow::Result<()>>(|(location, map)| {
+            std::fs::write(dir.join(file_name(location, "bin")), to_bytes(map)?)?;
+            Ok(())
+        })?;
+
+    let index = MapIndex::new(maps);
+
+    std::fs::write(dir.join("index.bin"), to_bytes(&index)?)?;
+
+    Ok(index)
+}
+
+/// Records which inputs produced an output directory.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub source: String,
+    pub revision: Option<String>,
+    pub version: String,
+    /// CRC32 of each input file, keyed by file name
+    pub inputs: BTreeMap<String, u32>,
+    pub maps: usize,
+    /// CRC32 of each serialized map, keyed by map file name
+    pub checksums: BTreeMap<String, u32>,
+    /// Content left out by the build profile
+    #[serde(default)]
+    pub removed: Vec<String>,
+    /// What [`changelog`] compares, keyed by map file name
+    #[serde(default)]
+    pub summaries: BTreeMap<String, MapSummary>,
+}
+
+/// Counts and checksums of a map's contents, recorded in the [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapSummary {
+    pub name: String,
+    pub music: String,
+    pub width: CoordinateInt,
+    pub height: CoordinateInt,
+    /// CRC32 of each row of tiles
+    pub rows: Vec<u32>,
+    pub npcs: usize,
+    pub trainers: usize,
+    pub warps: usize,
+    pub items: usize,
+    pub signs: usize,
+    /// Number of wild encounter tables
+    pub wild: usize,
+}
+
+impl MapSummary {
+    pub fn new(map: &WorldMap) -> anyhow::Result<Self> {
+        Ok(Self {
+            name: map.name.clone(),
+            music: map.music.to_string(),
+            width: map.width,
+            height: map.height,
+            rows: map
+                .tiles
+                .chunks((map.width as usize).max(1))
+                .map(|row| Ok(crc32fast::hash(&postcard::to_allocvec(row)?)))
+                .collect::<anyhow::Result<_>>()?,
+            npcs: map.npcs.len(),
+            trainers: map
+                .npcs
+                .values()
+                .filter(|npc| npc.trainer.is_some())
+                .count(),
+            warps: map.warps.len(),
+            items: map.items.len(),
+            signs: map.signs.len(),
+            wild: map.wild.as_ref().map(|wild| wild.len()).unwrap_or_default(),
+        })
+    }
+}
+
+impl Manifest {
+    pub fn new(
+        source: &str,
+        revision: Option<String>,
+        inputs: &[(&str, &[u8])],
+        maps: &HashMap<Location, WorldMap>,
+    ) -> anyhow::Result<Self> {
+        let checksums = maps
+            .par_iter()
+            .map(|(location, map)| -> anyhow::Result<_> {
+                Ok((file_name(location, "bin"), crc32fast::hash(&to_bytes(map)?)))
+            })
+            .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+
+        let summaries = maps
+            .par_iter()
+            .map(|(location, map)| -> anyhow::Result<_> {
+                Ok((file_name(location, "bin"), MapSummary::new(map)?))
+            })
+            .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+
+        Ok(Self {
+            source: source.to_owned(),
+            revision,
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            inputs: inputs
+                .iter()
+                .map(|(name, data)| (name.to_string(), crc32fast::hash(data)))
+                .collect(),
+            maps: maps.len(),
+            checksums,
+            removed: Vec::new(),
+            summaries,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+}
+
+/// Describes how the maps changed between two manifests, one line per changed map,
+/// such as `Route 2: 1 trainer added, music changed`.
+pub fn changelog(old: &Manifest, new: &Manifest) -> Vec<String> {
+    fn count(changes: &mut Vec<String>, what: &str, old: usize, new: usize) {
+        let (difference, verb) = match new > old {
+            true => (new - old, "added"),
+            false => (old - new, "removed"),
+        };
+        match difference {
+            0 => (),
+            1 => changes.push(format!("1 {} {}", what, verb)),
+            n => changes.push(format!("{} {}s {}", n, what, verb)),
+        }
+    }
+
+    let mut lines = Vec::new();
+
+    for (file, summary) in new.summaries.iter() {
+        let old = match old.summaries.get(file) {
+            Some(old) => old,
+            None => {
+                lines.push(format!("{}: added", summary.name));
+                continue;
+            }
+        };
+
+        if old == summary {
+            continue;
+        }
+
+        let mut changes = Vec::new();
+
+        if old.name != summary.name {
+            changes.push(format!("renamed from {}", old.name));
+        }
+        if old.music != summary.music {
+            changes.push("music changed".to_owned());
+        }
+        if (old.width, old.height) != (summary.width, summary.height) {
+            changes.push(format!(
+                "resized from {}x{} to {}x{}",
+                old.width, old.height, summary.width, summary.height
+            ));
+        } else {
+            let rows = old
+                .rows
+                .iter()
+                .zip(summary.rows.iter())
+                .filter(|(a, b)| a != b)
+                .count();
+            match rows {
+                0 => (),
+                1 => changes.push("tiles changed in 1 row".to_owned()),
+                n => changes.push(format!("tiles changed in {} rows", n)),
+            }
+        }
+        count(
+            &mut changes,
+            "NPC",
+            old.npcs - old.trainers,
+            summary.npcs - summary.trainers,
+        );
+        count(&mut changes, "trainer", old.trainers, summary.trainers);
+        count(&mut changes, "warp", old.warps, summary.warps);
+        count(&mut changes, "item", old.items, summary.items);
+        count(&mut changes, "sign", old.signs, summary.signs);
+        count(&mut changes, "wild table", old.wild, summary.wild);
+
+        if changes.is_empty() {
+            changes.push("changed".to_owned());
+        }
+
+        lines.push(format!("{}: {}", summary.name, changes.join(", ")));
+    }
+
+    for (file, summary) in old.summaries.iter() {
+        if !new.summaries.contains_key(file) {
+            lines.push(format!("{}: removed", summary.name));
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_round_trip_and_reject_other_schemas() {
+        let value = vec!["MAP_PALLET_TOWN".to_owned(), "MAP_ROUTE1".to_owned()];
+        let bytes = to_bytes(&value).unwrap();
+        assert_eq!(&bytes[..MAGIC.len()], MAGIC);
+        assert_eq!(from_bytes::<Vec<String>>(&bytes).unwrap(), value);
+
+        let mut old = postcard::to_allocvec(&Header {
+            schema: SCHEMA_VERSION + 1,
+            ..Header::current()
+        })
+        .unwrap();
+        old.extend(postcard::to_allocvec(&value).unwrap());
+        assert!(matches!(
+            from_bytes::<Vec<String>>(&old),
+            Err(HeaderError::Schema(schema)) if schema == SCHEMA_VERSION + 1
+        ));
+        assert!(matches!(
+            from_bytes::<Vec<String>>(b"PK\x03\x04"),
+            Err(HeaderError::Magic | HeaderError::Postcard(..))
+        ));
+    }
+
+    fn summary(name: &str) -> MapSummary {
+        MapSummary {
+            name: name.to_owned(),
+            music: "pallet".to_owned(),
+            width: 24,
+            height: 20,
+            rows: vec![0; 20],
+            npcs: 3,
+            trainers: 1,
+            warps: 2,
+            items: 0,
+            signs: 4,
+            wild: 0,
+        }
+    }
+
+    #[test]
+    fn changelog_describes_map_changes() {
+        let mut old = Manifest::default();
+        let mut new = Manifest::default();
+        for (file, name) in [("pallet.bin", "Pallet Town"), ("route1.bin", "Route 1")] {
+            old.summaries.insert(file.to_owned(), summary(name));
+        }
+
+        let mut pallet = summary("Pallet Town");
+        pallet.rows[3] = 1;
+        pallet.npcs = 5;
+        pallet.warps = 1;
+        new.summaries.insert("pallet.bin".to_owned(), pallet);
+        new.summaries
+            .insert("viridian.bin".to_owned(), summary("Viridian City"));
+
+        assert_eq!(
+            changelog(&old, &new),
+            [
+                "Pallet Town: tiles changed in 1 row, 2 NPCs added, 1 warp removed",
+                "Viridian City: added",
+                "Route 1: removed",
+            ]
+        );
+    }
+}
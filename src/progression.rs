@@ -0,0 +1,122 @@
+//! Coarse story progression graph, built from the flags map scripts set and check.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use crate::{map::JsonMap, ParsedData};
+
+/// Which flags each map sets and which flags gate it, keyed by pret map id.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ProgressionGraph {
+    pub maps: BTreeMap<String, ProgressionNode>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressionNode {
+    /// Flags set by the map's scripts
+    pub sets: BTreeSet<String>,
+    /// Flags checked by the map's scripts
+    pub checks: BTreeSet<String>,
+    /// Flags that hide an object event on the map, such as a Snorlax blocking a route
+    pub obstacles: BTreeSet<String>,
+    /// Maps reachable from this one through warps and connections
+    pub exits: BTreeSet<String>,
+}
+
+/// Flags a map depends on and the maps that set them.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Unlock {
+    pub set_by: BTreeSet<String>,
+    pub gates: BTreeSet<String>,
+}
+
+impl ProgressionGraph {
+    pub fn new(data: &ParsedData) -> Self {
+        Self {
+            maps: data
+                .maps
+                .par_iter()
+                .map(|map| (map.key().clone(), ProgressionNode::new(data, map.value())))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// For every flag, the maps that set it and the maps it gates.
+    pub fn unlocks(&self) -> BTreeMap<String, Unlock> {
+        let mut unlocks = BTreeMap::<String, Unlock>::new();
+        for (id, node) in self.maps.iter() {
+            for flag in node.sets.iter() {
+                unlocks
+                    .entry(flag.clone())
+                    .or_default()
+                    .set_by
+                    .insert(id.clone());
+            }
+            for flag in node.checks.iter().chain(node.obstacles.iter()) {
+                unlocks
+                    .entry(flag.clone())
+                    .or_default()
+                    .gates
+                    .insert(id.clone());
+            }
+        }
+        unlocks
+    }
+}
+
+impl ProgressionNode {
+    fn new(data: &ParsedData, map: &JsonMap) -> Self {
+        let mut node = Self::default();
+
+        for script in data
+            .scripts
+            .iter()
+            .filter(|script| data.is_map_script(script.key(), &map.data.name))
+        {
+            for command in script.commands.iter() {
+                let flags = command.arguments.iter().filter(|arg| is_flag(arg)).cloned();
+                match command.command.as_str() {
+                    "setflag" => node.sets.extend(flags),
+                    // clearing a flag only shows hidden object events again
+                    "clearflag" => (),
+                    _ => node.checks.extend(flags),
+                }
+            }
+        }
+
+        node.obstacles.extend(
+            map.data
+                .object_events
+                .iter()
+                .map(|event| &event.flag)
+                .filter(|flag| is_flag(flag))
+                .cloned(),
+        );
+
+        node.exits.extend(
+            map.data
+                .warp_events
+                .iter()
+                .map(|warp| warp.destination.clone())
+                .chain(
+                    map.data
+                        .connections
+                        .iter()
+                        .flatten()
+                        .map(|connection| connection.map.clone()),
+                )
+                .filter(|destination| destination != &map.data.id),
+        );
+
+        node
+    }
+}
+
+fn is_flag(arg: &str) -> bool {
+    arg.starts_with("FLAG_")
+}
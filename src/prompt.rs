@@ -0,0 +1,151 @@
+//! Asks for mappings of unmapped names on the terminal.
+
+use std::{
+    io::{BufRead, IsTerminal, Write},
+    path::Path,
+};
+
+use crate::{music_name, MissingMappings, NameMappings};
+
+/// Whether the user can be prompted.
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// Suggests a display name for a map, such as `Pallet Town Players House 1F` for `PalletTown_PlayersHouse_1F`.
+pub fn suggest_name(map: &str) -> String {
+    let mut name = String::with_capacity(map.len() + 4);
+    let mut previous: Option<char> = None;
+    for c in map.chars() {
+        match c {
+            '_' => name.push(' '),
+            c => {
+                if let Some(previous) = previous {
+                    if c.is_ascii_uppercase() && previous.is_ascii_lowercase() {
+                        name.push(' ');
+                    }
+                }
+                name.push(c);
+            }
+        }
+        previous = Some(c);
+    }
+    name
+}
+
+enum Answer {
+    Value(String),
+    Skip,
+    Quit,
+}
+
+fn ask(question: &str, suggestion: Option<&str>) -> anyhow::Result<Answer> {
+    match suggestion {
+        Some(suggestion) => print!("{} [{}]: ", question, suggestion),
+        None => print!("{}: ", question),
+    }
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        return Ok(Answer::Quit);
+    }
+    Ok(match line.trim() {
+        "q" => Answer::Quit,
+        "-" => Answer::Skip,
+        "" => match suggestion {
+            Some(suggestion) => Answer::Value(suggestion.to_owned()),
+            None => Answer::Skip,
+        },
+        value => Answer::Value(value.to_owned()),
+    })
+}
+
+/// Prompts for every missing map name, music and tileset mapping,
+/// adding the answers to `mappings` and writing them to the mappings file at `path`.
+///
+/// Pressing enter accepts the suggestion, `-` skips a name and `q` stops prompting.
+/// Returns the number of mappings added.
+pub fn resolve(
+    mappings: &mut NameMappings,
+    missing: &MissingMappings,
+    path: &Path,
+) -> anyhow::Result<usize> {
+    let mut added = NameMappings::default();
+    let mut count = 0;
+
+    println!("Enter a mapping, press enter to accept the suggestion, - to skip or q to stop");
+
+    'prompt: {
+        for map in missing.map_names.iter() {
+            match ask(&format!("Name of map {}", map), Some(&suggest_name(map)))? {
+                Answer::Value(name) => {
+                    added.map.name.insert(map.clone(), name);
+                    count += 1;
+                }
+                Answer::Skip => (),
+                Answer::Quit => break 'prompt,
+            }
+        }
+        for music in missing.music.iter() {
+            let suggestion = music_name(music).map(|name| name.to_string());
+            match ask(&format!("Id of music {}", music), suggestion.as_deref())? {
+                Answer::Value(id) => match id.parse() {
+                    Ok(id) => {
+                        added.music.insert(music.clone(), id);
+                        count += 1;
+                    }
+                    Err(..) => eprintln!("{} is longer than 16 characters", id),
+                },
+                Answer::Skip => (),
+                Answer::Quit => break 'prompt,
+            }
+        }
+        for (tilesets, primary) in [
+            (&missing.primary_tilesets, true),
+            (&missing.secondary_tilesets, false),
+        ] {
+            for tileset in tilesets.iter() {
+                let question = match primary {
+                    true => format!("Palette of primary tileset {}", tileset),
+                    false => format!("Palette of secondary tileset {}", tileset),
+                };
+                match ask(&question, None)? {
+                    Answer::Value(palette) => match palette.parse() {
+                        Ok(palette) => {
+                            match primary {
+                                true => added.palettes.primary.insert(tileset.clone(), palette),
+                                false => added.palettes.secondary.insert(tileset.clone(), palette),
+                            };
+                            count += 1;
+                        }
+                        Err(..) => eprintln!("{} is not a palette id", palette),
+                    },
+                    Answer::Skip => (),
+                    Answer::Quit => break 'prompt,
+                }
+            }
+        }
+    }
+
+    if count == 0 {
+        return Ok(0);
+    }
+
+    let mut file = match path.exists() {
+        true => NameMappings::from_file(path)?,
+        false => NameMappings::default(),
+    };
+
+    file.map.name.extend(added.map.name.clone());
+    file.music.extend(added.music.clone());
+    file.palettes.primary.extend(added.palettes.primary.clone());
+    file.palettes
+        .secondary
+        .extend(added.palettes.secondary.clone());
+
+    std::fs::write(path, file.to_file(path)?)?;
+
+    mappings.merge(added);
+
+    Ok(count)
+}
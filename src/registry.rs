@@ -0,0 +1,213 @@
+//! Numeric ids for the flags and variables used by scripts.
+
+use std::{collections::BTreeMap, ops::Range};
+
+use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{Scripts, Source};
+
+/// Path of pret's flag definitions.
+pub const FLAGS_HEADER: &str = "include/constants/flags.h";
+
+/// Path of pret's variable definitions.
+pub const VARS_HEADER: &str = "include/constants/vars.h";
+
+/// What to do when a flag or variable falls in a reserved range.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Collision {
+    /// Move it to the next id that is neither reserved nor used
+    #[default]
+    Remap,
+    Error,
+}
+
+/// Id ranges the engine keeps for itself.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegistryConfig {
+    pub reserved_flags: Vec<Range<u16>>,
+    pub reserved_vars: Vec<Range<u16>>,
+    pub on_collision: Collision,
+}
+
+/// Every flag and variable the scripts use, numbered like pret's headers.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Registry {
+    pub flags: BTreeMap<String, u16>,
+    pub vars: BTreeMap<String, u16>,
+}
+
+impl Registry {
+    /// Names without a definition in the headers are numbered after the highest defined id.
+    pub fn new(scripts: &Scripts, flags_header: &str, vars_header: &str) -> Self {
+        let mut flags = Vec::new();
+        let mut vars = Vec::new();
+        for script in scripts.iter() {
+            for arg in script.commands.iter().flat_map(|c| c.arguments.iter()) {
+                if arg.starts_with("FLAG_") {
+                    flags.push(arg.as_str());
+                } else if arg.starts_with("VAR_") {
+                    vars.push(arg.as_str());
+                }
+            }
+        }
+        Self {
+            flags: number(flags, &parse_defines(flags_header)),
+            vars: number(vars, &parse_defines(vars_header)),
+        }
+    }
+
+    /// Creates a registry with the headers of a source.
+    pub fn from_source(source: &dyn Source, scripts: &Scripts) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            scripts,
+            &source.text(FLAGS_HEADER)?,
+            &source.text(VARS_HEADER)?,
+        ))
+    }
+
+    /// Checks ids against the reserved ranges, returning a message for every remapped id.
+    pub fn reserve(&mut self, config: &RegistryConfig) -> anyhow::Result<Vec<String>> {
+        let mut remapped = reserve("flag", &mut self.flags, &config.reserved_flags, config)?;
+        remapped.extend(reserve(
+            "variable",
+            &mut self.vars,
+            &config.reserved_vars,
+            config,
+        )?);
+        Ok(remapped)
+    }
+}
+
+fn number(names: Vec<&str>, defines: &HashMap<String, u16>) -> BTreeMap<String, u16> {
+    let mut ids = BTreeMap::new();
+    let mut next = defines
+        .values()
+        .max()
+        .map(|max| max + 1)
+        .unwrap_or_default();
+    let mut undefined = Vec::new();
+    for name in names {
+        match defines.get(name) {
+            Some(id) => {
+                ids.insert(name.to_owned(), *id);
+            }
+            None => undefined.push(name),
+        }
+    }
+    undefined.sort_unstable();
+    undefined.dedup();
+    for name in undefined {
+        ids.insert(name.to_owned(), next);
+        next += 1;
+    }
+    ids
+}
+
+fn reserve(
+    kind: &str,
+    ids: &mut BTreeMap<String, u16>,
+    reserved: &[Range<u16>],
+    config: &RegistryConfig,
+) -> anyhow::Result<Vec<String>> {
+    let is_reserved = |id: u16| reserved.iter().any(|range| range.contains(&id));
+    let colliding = ids
+        .iter()
+        .filter(|(.., id)| is_reserved(**id))
+        .map(|(name, ..)| name.clone())
+        .collect::<Vec<_>>();
+    let mut used = ids.values().copied().collect::<HashSet<_>>();
+    let mut remapped = Vec::new();
+    for name in colliding {
+        let id = ids[&name];
+        if config.on_collision == Collision::Error {
+            anyhow::bail!("{} {} uses reserved id {:#x}", kind, name, id);
+        }
+        let new = (0..=u16::MAX)
+            .find(|id| !is_reserved(*id) && !used.contains(id))
+            .ok_or_else(|| anyhow::anyhow!("No free id left for {} {}", kind, name))?;
+        used.insert(new);
+        ids.insert(name.clone(), new);
+        remapped.push(format!("{} {} {:#x} -> {:#x}", kind, name, id, new));
+    }
+    Ok(remapped)
+}
+
+/// Evaluates `#define`s whose values are numbers, or sums of numbers and earlier defines.
+pub fn parse_defines(header: &str) -> HashMap<String, u16> {
+    let mut defines = HashMap::new();
+    for line in header.lines() {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("#define") {
+            continue;
+        }
+        let name = match words.next() {
+            Some(name) => name,
+            None => continue,
+        };
+        let value = words
+            .take_while(|word| !word.starts_with("//"))
+            .collect::<String>();
+        if let Some(value) = evaluate(&value, &defines) {
+            defines.insert(name.to_owned(), value);
+        }
+    }
+    defines
+}
+
+fn evaluate(expression: &str, defines: &HashMap<String, u16>) -> Option<u16> {
+    let expression = expression.replace(['(', ')'], "");
+    let mut total = 0i64;
+    let mut rest = expression.as_str();
+    while !rest.is_empty() {
+        let end = rest[1..]
+            .find(['+', '-'])
+            .map(|end| end + 1)
+            .unwrap_or(rest.len());
+        let (term, next) = rest.split_at(end);
+        let (sign, term) = match term.strip_prefix('-') {
+            Some(term) => (-1, term),
+            None => (1, term.trim_start_matches('+')),
+        };
+        let value = match term.strip_prefix("0x") {
+            Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+            None => match term.parse::<i64>() {
+                Ok(value) => value,
+                Err(..) => *defines.get(term)? as i64,
+            },
+        };
+        total += sign * value;
+        rest = next;
+    }
+    u16::try_from(total).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sums_of_earlier_defines() {
+        let header = "\
+#define FLAG_TEMP_1                 0x1
+#define SYSTEM_FLAGS                0x800
+#define FLAG_SYS_POKEMON_GET        (SYSTEM_FLAGS + 0x28) // FLAG_0x828
+#define FLAGS_COUNT                 (DAILY_FLAGS_END + 1)
+";
+        let defines = parse_defines(header);
+        assert_eq!(defines.get("FLAG_TEMP_1"), Some(&0x1));
+        assert_eq!(defines.get("FLAG_SYS_POKEMON_GET"), Some(&0x828));
+        assert_eq!(defines.get("FLAGS_COUNT"), None);
+    }
+
+    #[test]
+    fn evaluates_differences() {
+        let defines = [("SYSTEM_FLAGS".to_owned(), 0x800)]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        assert_eq!(evaluate("SYSTEM_FLAGS-0x1", &defines), Some(0x7FF));
+        assert_eq!(evaluate("1-2", &defines), None);
+    }
+}
@@ -0,0 +1,112 @@
+//! A small example project to start from, written by `world-gen scaffold <dir>`.
+
+use std::path::Path;
+
+/// Maps converted by the example, which each match only one pret map.
+pub const EXAMPLE_MAPS: [&str; 3] = [
+    "MAP_VIRIDIAN_FOREST",
+    "MAP_MT_MOON_1F",
+    "MAP_DIGLETTS_CAVE_B1F",
+];
+
+const README: &str = "# world-gen example
+
+Converts three maps from pokefirered and loads them back with worldlib.
+
+Generate the maps from the world-gen repository with
+
+    cargo run --example run -- --config {dir}/world-gen.toml
+
+then read them with the code in `loader.rs`.
+";
+
+const MAPPINGS: &str = "// Project mappings layered over the ones built into world-gen.
+// Only the entries that should change have to be listed here.
+(
+    map: (
+        name: {
+            \"MtMoon_1F\": \"Mt. Moon\",
+        },
+    ),
+)
+";
+
+const EDITS: &str = "// Changes made to the converted maps, keyed by their location.
+(
+    maps: {},
+)
+";
+
+const LOADER: &str = "//! Reads the maps written by world-gen.
+
+use firecore_world_builder::world::map::WorldMap;
+
+fn main() -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(\"{dir}/output/maps/files\")? {
+        let path = entry?.path();
+        let map: WorldMap = firecore_world_gen::output::from_bytes(&std::fs::read(&path)?)?;
+        println!(\"Loaded {} ({}x{}) from {:?}\", map.name, map.width, map.height, path);
+    }
+    Ok(())
+}
+";
+
+fn config(dir: &Path) -> String {
+    format!(
+        "# Options for world-gen, which can be overridden with WORLD_GEN_* variables and flags.
+
+# Convert only these maps, by pret map id prefix
+filter = [{}]
+mappings = {:?}
+user_mappings = {:?}
+edits = {:?}
+cache = {:?}
+prompt = false
+
+[output]
+dir = {:?}
+",
+        EXAMPLE_MAPS
+            .iter()
+            .map(|map| format!("\"{}\"", map))
+            .collect::<Vec<_>>()
+            .join(", "),
+        dir.join("mappings.ron"),
+        dir.join("mappings.user.ron"),
+        dir.join("edits.ron"),
+        dir.join("output"),
+        dir.join("output"),
+    )
+}
+
+/// Writes an example config, mappings, edits and loader into a directory,
+/// refusing to overwrite files that already exist.
+pub fn scaffold(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let files = [
+        (crate::config::CONFIG_FILE, config(dir)),
+        ("mappings.ron", MAPPINGS.to_owned()),
+        ("edits.ron", EDITS.to_owned()),
+        (
+            "loader.rs",
+            LOADER.replace("{dir}", &dir.display().to_string()),
+        ),
+        (
+            "README.md",
+            README.replace("{dir}", &dir.display().to_string()),
+        ),
+    ];
+
+    if let Some((name, ..)) = files.iter().find(|(name, ..)| dir.join(name).exists()) {
+        anyhow::bail!("{:?} already exists", dir.join(name));
+    }
+
+    std::fs::create_dir_all(dir)?;
+
+    files
+        .into_iter()
+        .map(|(name, contents)| {
+            std::fs::write(dir.join(name), contents)?;
+            Ok(name.to_owned())
+        })
+        .collect()
+}
@@ -0,0 +1,185 @@
+//! Minimal HTTP server for browsing the last generated output.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+};
+
+use crate::{output::Manifest, progression::ProgressionGraph};
+
+/// Serves the output directory until the process is stopped.
+///
+/// * `/` lists every map, filtered by the `q` query parameter
+/// * `/maps/<file>` previews a map
+/// * `/graph` lists the warps and connections between maps
+/// * `/manifest.json` and `/progression.json` are the raw reports
+pub fn serve(root: &Path, address: &str) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    println!("Serving {:?} on http://{}", root, address);
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Could not accept connection with error {}", err);
+                continue;
+            }
+        };
+        if let Err(err) = handle(root, &mut stream) {
+            eprintln!("Could not respond with error {}", err);
+        }
+    }
+    Ok(())
+}
+
+fn handle(root: &Path, stream: &mut TcpStream) -> anyhow::Result<()> {
+    let mut line = String::new();
+    BufReader::new(&*stream).read_line(&mut line)?;
+
+    let target = line.split_whitespace().nth(1).unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let response = match path {
+        "/" => index(root, &param(query, "q").unwrap_or_default()),
+        "/graph" => graph(root),
+        "/manifest.json" | "/progression.json" => file(root.join(&path[1..]), "application/json"),
+        path => match path.strip_prefix("/maps/") {
+            Some(file) if !file.contains("..") && !file.contains('/') => preview(root, file),
+            _ => Err(anyhow::anyhow!("Not found")),
+        },
+    };
+
+    let (status, kind, body) = match response {
+        Ok((kind, body)) => ("200 OK", kind, body),
+        Err(err) => ("404 Not Found", "text/plain", err.to_string().into_bytes()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        kind,
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    Ok(())
+}
+
+type Response = anyhow::Result<(&'static str, Vec<u8>)>;
+
+fn file(path: PathBuf, kind: &'static str) -> Response {
+    Ok((kind, std::fs::read(path)?))
+}
+
+fn page(title: &str, body: String) -> Response {
+    Ok((
+        "text/html; charset=utf-8",
+        format!(
+            "<!DOCTYPE html><html><head><title>{0}</title></head><body>\
+             <p><a href=\"/\">Maps</a> | <a href=\"/graph\">Warp graph</a> | \
+             <a href=\"/manifest.json\">Manifest</a> | <a href=\"/progression.json\">Progression</a></p>\
+             <h1>{0}</h1>{1}</body></html>",
+            escape(title),
+            body
+        )
+        .into_bytes(),
+    ))
+}
+
+fn index(root: &Path, search: &str) -> Response {
+    let manifest = Manifest::load(&root.join("manifest.json"))?;
+    let search = search.to_lowercase();
+    let mut body = format!(
+        "<form><input name=\"q\" value=\"{}\"><button>Search</button></form>\
+         <p>{} maps from {}</p><ul>",
+        escape(&search),
+        manifest.maps,
+        escape(&manifest.source)
+    );
+    for (file, summary) in manifest.summaries.iter() {
+        if search.is_empty() || summary.name.to_lowercase().contains(&search) {
+            body.push_str(&format!(
+                "<li><a href=\"/maps/{}\">{}</a> ({}x{}, {} NPCs, {} warps)</li>",
+                escape(file),
+                escape(&summary.name),
+                summary.width,
+                summary.height,
+                summary.npcs,
+                summary.warps
+            ));
+        }
+    }
+    body.push_str("</ul>");
+    page("Maps", body)
+}
+
+fn preview(root: &Path, file: &str) -> Response {
+    let manifest = Manifest::load(&root.join("manifest.json"))?;
+    let summary = manifest
+        .summaries
+        .get(file)
+        .ok_or_else(|| anyhow::anyhow!("Unknown map {}", file))?;
+    let mut body = format!(
+        "<pre>{}</pre>",
+        escape(&serde_json::to_string_pretty(summary)?)
+    );
+    let copy = root
+        .join("maps")
+        .join("copies")
+        .join(Path::new(file).with_extension("world"));
+    if let Ok(copy) = std::fs::read_to_string(copy) {
+        body.push_str(&format!("<h2>Map</h2><pre>{}</pre>", escape(&copy)));
+    }
+    page(&summary.name, body)
+}
+
+fn graph(root: &Path) -> Response {
+    let graph: ProgressionGraph =
+        serde_json::from_slice(&std::fs::read(root.join("progression.json"))?)?;
+    let mut body = String::from("<ul>");
+    for (map, node) in graph.maps.iter() {
+        body.push_str(&format!("<li>{}<ul>", escape(map)));
+        for exit in node.exits.iter() {
+            body.push_str(&format!("<li>{}</li>", escape(exit)));
+        }
+        body.push_str("</ul></li>");
+    }
+    body.push_str("</ul>");
+    page("Warp graph", body)
+}
+
+fn param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .flat_map(|pair| pair.split_once('='))
+        .find(|(key, ..)| *key == name)
+        .map(|(.., value)| decode(value))
+}
+
+fn decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next().unwrap_or(b'0'), input.next().unwrap_or(b'0')];
+                bytes.push(
+                    std::str::from_utf8(&hex)
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                        .unwrap_or(b'?'),
+                );
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
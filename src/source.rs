@@ -0,0 +1,242 @@
+//! Where pret data is read from.
+
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{
+    map::{JsonMapData, JsonMapLayouts},
+    REPOSITORY,
+};
+
+/// Largest block data file, as pret limits maps to 0x2800 blocks of 2 bytes each.
+pub const MAX_BLOCKDATA_SIZE: u64 = 0x2800 * 2;
+
+/// Reads files from a copy of the pret repository.
+///
+/// Only [`Source::get`] has to be implemented; the other methods locate
+/// specific files relative to the root of the repository.
+pub trait Source: Send + Sync {
+    /// Gets a file by its path relative to the root of the repository.
+    fn get(&self, path: &str) -> anyhow::Result<Vec<u8>>;
+
+    /// Gets a file, failing if it is larger than `limit` bytes.
+    ///
+    /// Sources that download files should stop reading at the limit.
+    fn get_limited(&self, path: &str, limit: u64) -> anyhow::Result<Vec<u8>> {
+        let bytes = self.get(path)?;
+        match bytes.len() as u64 > limit {
+            true => Err(anyhow::anyhow!("{} is larger than {} bytes", path, limit)),
+            false => Ok(bytes),
+        }
+    }
+
+    /// Describes the source in the output manifest.
+    fn name(&self) -> String;
+
+    /// Gets the commit hash of the files, if the source knows it.
+    fn revision(&self) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    fn text(&self, path: &str) -> anyhow::Result<String> {
+        Ok(String::from_utf8(self.get(path)?)?)
+    }
+
+    fn layouts(&self) -> anyhow::Result<JsonMapLayouts> {
+        Ok(serde_json::from_slice(
+            &self.get("data/layouts/layouts.json")?,
+        )?)
+    }
+
+    fn map_groups(&self) -> anyhow::Result<serde_json::Value> {
+        Ok(serde_json::from_slice(
+            &self.get("data/maps/map_groups.json")?,
+        )?)
+    }
+
+    fn map_json(&self, map: &str) -> anyhow::Result<JsonMapData> {
+        Ok(serde_json::from_slice(
+            &self.get(&format!("data/maps/{}/map.json", map))?,
+        )?)
+    }
+
+    fn map_scripts(&self, map: &str) -> anyhow::Result<String> {
+        self.text(&format!("data/maps/{}/scripts.inc", map))
+    }
+
+    fn map_text(&self, map: &str) -> anyhow::Result<String> {
+        self.text(&format!("data/maps/{}/text.inc", map))
+    }
+
+    /// Gets block or border data at a path taken from a map layout.
+    fn blockdata(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        self.get_limited(path, MAX_BLOCKDATA_SIZE)
+    }
+}
+
+/// Downloads files from a web server, such as GitHub's raw file host.
+pub struct HttpSource {
+    pub root: String,
+}
+
+impl Default for HttpSource {
+    fn default() -> Self {
+        Self::new("master")
+    }
+}
+
+impl HttpSource {
+    /// Downloads from a branch, tag or commit of pret's repository on GitHub.
+    pub fn new(reference: &str) -> Self {
+        Self {
+            root: format!("{}/{}", REPOSITORY, reference),
+        }
+    }
+
+    /// Downloads from the commit the branch or tag points at, so every file
+    /// comes from the commit recorded as the source's [revision](Source::revision).
+    pub fn pinned(self) -> anyhow::Result<Self> {
+        Ok(match self.revision()? {
+            Some(commit) => Self::new(&commit),
+            None => self,
+        })
+    }
+}
+
+impl Source for HttpSource {
+    fn get(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        crate::http::get(&format!("{}/{}", self.root, path))
+    }
+
+    fn get_limited(&self, path: &str, limit: u64) -> anyhow::Result<Vec<u8>> {
+        crate::http::get_limited(&format!("{}/{}", self.root, path), limit)
+    }
+
+    fn name(&self) -> String {
+        self.root.clone()
+    }
+
+    fn revision(&self) -> anyhow::Result<Option<String>> {
+        let reference = match self.root.strip_prefix(REPOSITORY) {
+            Some(reference) => reference.trim_start_matches('/'),
+            None => return Ok(None),
+        };
+        match commit_hash(reference) {
+            Some(commit) => Ok(Some(commit)),
+            None => crate::source_revision(reference).map(Some),
+        }
+    }
+}
+
+/// Reads files from a local clone of the repository.
+pub struct DirectorySource {
+    pub root: PathBuf,
+}
+
+impl Source for DirectorySource {
+    fn get(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        Ok(std::fs::read(self.root.join(path))?)
+    }
+
+    fn name(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    /// Reads the commit checked out in the directory, if it is a git clone.
+    fn revision(&self) -> anyhow::Result<Option<String>> {
+        let git = self.root.join(".git");
+        let head = match std::fs::read_to_string(git.join("HEAD")) {
+            Ok(head) => head.trim().to_owned(),
+            Err(..) => return Ok(None),
+        };
+        let reference = match head.strip_prefix("ref: ") {
+            Some(reference) => reference,
+            None => return Ok(commit_hash(&head)),
+        };
+        if let Ok(commit) = std::fs::read_to_string(git.join(reference)) {
+            return Ok(commit_hash(commit.trim()));
+        }
+        // refs that were packed by git gc are listed as `<commit> <ref>`
+        Ok(std::fs::read_to_string(git.join("packed-refs"))
+            .unwrap_or_default()
+            .lines()
+            .flat_map(|line| line.split_once(' '))
+            .find(|(.., name)| *name == reference)
+            .and_then(|(commit, ..)| commit_hash(commit)))
+    }
+}
+
+/// Reads files from a zip archive of the repository, such as one downloaded from GitHub.
+pub struct ArchiveSource {
+    path: PathBuf,
+    /// Directory every file in the archive is inside of, if any
+    prefix: String,
+    archive: Mutex<zip::ZipArchive<File>>,
+}
+
+impl ArchiveSource {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let archive = zip::ZipArchive::new(File::open(path)?)?;
+        let prefix = archive
+            .file_names()
+            .next()
+            .and_then(|name| name.split_once('/'))
+            .map(|(dir, ..)| format!("{}/", dir))
+            .filter(|dir| archive.file_names().all(|name| name.starts_with(dir)))
+            .unwrap_or_default();
+        Ok(Self {
+            path: path.to_owned(),
+            prefix,
+            archive: Mutex::new(archive),
+        })
+    }
+}
+
+impl Source for ArchiveSource {
+    fn get(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        let mut archive = self
+            .archive
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Archive lock was poisoned"))?;
+        let mut file = archive.by_name(&format!("{}{}", self.prefix, path))?;
+        let mut bytes = Vec::with_capacity(file.size() as _);
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    /// Reads the commit GitHub writes into the comment of the archives it serves.
+    fn revision(&self) -> anyhow::Result<Option<String>> {
+        let archive = self
+            .archive
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Archive lock was poisoned"))?;
+        Ok(std::str::from_utf8(archive.comment())
+            .ok()
+            .and_then(|comment| commit_hash(comment.trim())))
+    }
+}
+
+/// Checks that text is a full commit hash.
+fn commit_hash(text: &str) -> Option<String> {
+    (text.len() == 40 && text.chars().all(|c| c.is_ascii_hexdigit())).then(|| text.to_owned())
+}
+
+/// Opens a local source, treating `.zip` files as archives and anything else as a directory.
+pub fn open(path: &Path) -> anyhow::Result<Box<dyn Source>> {
+    Ok(
+        match path.extension().and_then(|e| e.to_str()) == Some("zip") {
+            true => Box::new(ArchiveSource::open(path)?),
+            false => Box::new(DirectorySource {
+                root: path.to_owned(),
+            }),
+        },
+    )
+}
@@ -0,0 +1,181 @@
+//! Tileset files in pret's repository.
+
+use std::collections::BTreeMap;
+
+use firecore_world_builder::world::map::PaletteId;
+use hashbrown::{HashMap, HashSet};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{NameMappings, ParsedData, Source};
+
+/// Prefix of pret's tileset symbols.
+pub const TILESET_PREFIX: &str = "gTileset_";
+
+/// pret's tileset headers, which name the metatiles of each tileset.
+pub const TILESET_HEADERS: &str = "src/data/tilesets/headers.h";
+
+/// pret's table of metatiles, which are included from the directories of their tilesets.
+pub const TILESET_METATILES: &str = "src/data/tilesets/metatiles.h";
+
+/// Directories of tilesets keyed by their symbols, found by [`parse_tileset_dirs`].
+pub type TilesetDirs = HashMap<String, String>;
+
+/// Finds the directory of each tileset in the [headers](TILESET_HEADERS)
+/// from the path its [metatiles](TILESET_METATILES) are included from.
+pub fn parse_tileset_dirs(headers: &str, metatiles: &str) -> TilesetDirs {
+    let mut tilesets = HashMap::new();
+    let mut tileset = None;
+    for line in headers.lines().map(str::trim) {
+        if let Some(declaration) = line.strip_prefix("const struct Tileset ") {
+            tileset = declaration.split_whitespace().next();
+        } else if let Some(symbol) = line.strip_prefix(".metatiles") {
+            let symbol = symbol
+                .trim_start_matches(|c| c == ' ' || c == '=')
+                .trim_end_matches(',');
+            if let Some(tileset) = tileset.take() {
+                tilesets.insert(symbol.trim(), tileset);
+            }
+        }
+    }
+
+    metatiles
+        .lines()
+        .flat_map(|line| {
+            let (declaration, path) = line.split_once("INCBIN_U16(\"")?;
+            let symbol = declaration.split('[').next()?.split_whitespace().last()?;
+            let dir = path.split_once('"')?.0.strip_suffix("/metatiles.bin")?;
+            Some(((*tilesets.get(symbol)?).to_owned(), dir.to_owned()))
+        })
+        .collect()
+}
+
+/// Gets the directory of a tileset, from `dirs` or otherwise by its name,
+/// such as `data/tilesets/secondary/pallet_town` for `gTileset_PalletTown`.
+pub fn tileset_dir(dirs: &TilesetDirs, tileset: &str, primary: bool) -> Option<String> {
+    if let Some(dir) = dirs.get(tileset) {
+        return Some(dir.clone());
+    }
+    let name = tileset.strip_prefix(TILESET_PREFIX)?;
+    Some(format!(
+        "data/tilesets/{}/{}",
+        match primary {
+            true => "primary",
+            false => "secondary",
+        },
+        crate::convert::snake_case(name)
+    ))
+}
+
+/// CRC32 of a tileset's graphics and palettes.
+pub fn hash(source: &dyn Source, dir: &str) -> anyhow::Result<u32> {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&source.get(&format!("{}/tiles.png", dir))?);
+    for palette in 0..16 {
+        if let Ok(palette) = source.get(&format!("{}/palettes/{:02}.pal", dir, palette)) {
+            hasher.update(&palette);
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+/// Result of [`detect_palettes`].
+#[derive(Debug, Default)]
+pub struct DetectedPalettes {
+    pub primary: BTreeMap<String, PaletteId>,
+    pub secondary: BTreeMap<String, PaletteId>,
+    /// Hashes of tilesets that match no known palette, to be assigned by hand
+    pub unknown: BTreeMap<String, u32>,
+}
+
+/// Hashes every tileset without a palette mapping and matches it against
+/// the known engine palettes in [`PaletteMappings::hashes`](crate::PaletteMappings::hashes).
+pub fn detect_palettes(
+    source: &dyn Source,
+    mappings: &NameMappings,
+    data: &ParsedData,
+) -> DetectedPalettes {
+    let mut tilesets = HashSet::new();
+    for map in data.maps.iter() {
+        let layout = &map.value().layout;
+        if !mappings
+            .palettes
+            .primary
+            .contains_key(&layout.primary_tileset)
+        {
+            tilesets.insert((layout.primary_tileset.clone(), true));
+        }
+        if !mappings
+            .palettes
+            .secondary
+            .contains_key(&layout.secondary_tileset)
+        {
+            tilesets.insert((layout.secondary_tileset.clone(), false));
+        }
+    }
+
+    let hashes = tilesets
+        .into_par_iter()
+        .flat_map(|(tileset, primary)| {
+            let dir = tileset_dir(&data.tileset_dirs, &tileset, primary)?;
+            match hash(source, &dir) {
+                Ok(hash) => Some((tileset, primary, hash)),
+                Err(err) => {
+                    eprintln!("Could not hash tileset {} with error {}", tileset, err);
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut detected = DetectedPalettes::default();
+
+    for (tileset, primary, hash) in hashes {
+        match (mappings.palettes.hashes.get(&hash), primary) {
+            (Some(palette), true) => {
+                detected.primary.insert(tileset, *palette);
+            }
+            (Some(palette), false) => {
+                detected.secondary.insert(tileset, *palette);
+            }
+            (None, ..) => {
+                detected.unknown.insert(tileset, hash);
+            }
+        }
+    }
+
+    detected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tileset_dirs_follow_included_metatiles() {
+        let headers = r#"
+const struct Tileset gTileset_SSAnne =
+{
+    .isCompressed = TRUE,
+    .isSecondary = TRUE,
+    .tiles = gTilesetTiles_SSAnne,
+    .palettes = gTilesetPalettes_SSAnne,
+    .metatiles = gMetatiles_SSAnne,
+    .metatileAttributes = gMetatileAttributes_SSAnne,
+    .callback = NULL,
+};
+"#;
+        let metatiles = r#"
+const u16 gMetatiles_SSAnne[] = INCBIN_U16("data/tilesets/secondary/ss_anne/metatiles.bin");
+const u32 gMetatileAttributes_SSAnne[] = INCBIN_U32("data/tilesets/secondary/ss_anne/metatile_attributes.bin");
+"#;
+        let dirs = parse_tileset_dirs(headers, metatiles);
+        assert_eq!(
+            tileset_dir(&dirs, "gTileset_SSAnne", false).as_deref(),
+            Some("data/tilesets/secondary/ss_anne")
+        );
+        assert_eq!(
+            tileset_dir(&dirs, "gTileset_PalletTown", false).as_deref(),
+            Some("data/tilesets/secondary/pallet_town")
+        );
+    }
+}